@@ -1,4 +1,4 @@
-use crate::constants::{MUSIC_TRACKS, DROP_SOUND, CLEAR_SOUND};
+use crate::config::GameConfig;
 use amethyst::audio::OggFormat;
 use amethyst::{
     assets::{AssetStorage, Loader},
@@ -23,12 +23,14 @@ fn load_audio_track(loader: &Loader, world: &World, file: &str) -> SourceHandle
 /// the background tracks as well as the sound effects, but for now
 /// we'll just work on sound effects.
 pub fn initialise_audio(world: &mut World) {
+    let paths = world.read_resource::<GameConfig>().audio.clone();
     let (sound_effects, music) = {
         let loader = world.read_resource::<Loader>();
 
         let mut sink = world.write_resource::<AudioSink>();
         sink.set_volume(0.25); // Music is a bit loud, reduce the volume.
-        let music = MUSIC_TRACKS
+        let music = paths
+            .music
             .iter()
             .map(|file| load_audio_track(&loader, &world, file))
             .collect::<Vec<_>>()
@@ -36,8 +38,8 @@ pub fn initialise_audio(world: &mut World) {
             .cycle();
         let music = Music { music };
         let sound = Sounds {
-            drop_sfx: load_audio_track(&loader, &world, DROP_SOUND),
-            clear_sfx: load_audio_track(&loader, &world, CLEAR_SOUND),
+            drop_sfx: load_audio_track(&loader, &world, &paths.drop_sound),
+            clear_sfx: load_audio_track(&loader, &world, &paths.clear_sound),
         };
 
         (sound, music)
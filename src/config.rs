@@ -0,0 +1,281 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::engine::{GravityCurve, MarathonGoal, ModeKind, RandomizerKind};
+
+/*
+The rules that can be changed without recompiling, they are loaded from config/gameplay.ron.
+Anything missing in the file just keeps its default value, a field we do not know is an error
+so a typo does not go unnoticed.
+*/
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameConfig {
+    pub mode: ModeKind,
+    pub board_width: usize,
+    pub board_height: usize,
+    pub randomizer: RandomizerKind,
+    pub preview_count: usize,
+    pub ghost_piece: bool,
+    pub lock_delay: f32,
+    pub lock_resets: u32,
+    pub gravity: GravityCurve,
+    pub start_level: u32,
+    pub lines_per_level: u32,
+    pub twenty_g_level: Option<u32>, // from this level on the pieces drop to the floor right away, never if not set
+    pub player_name: String,         // the name that goes on the records
+    pub records_file: Option<String>, // where the records are kept, the data folder of the user if not set
+    pub seed: Option<u64>, // the same seed gives the same pieces, a new one every game if not set
+    pub audio: AudioConfig,
+}
+
+// Paths inside the assets folder.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AudioConfig {
+    pub clear_sound: String,
+    pub drop_sound: String,
+    pub music: Vec<String>,
+}
+
+/*
+How the controls feel, loaded from config/handling.ron. Every player likes these a bit different.
+The times are in seconds, the game rounds them to whole frames of 1/60 of a second.
+*/
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HandlingConfig {
+    pub das: f32,              // how long left or right is held before it starts repeating
+    pub arr: f32,              // time between the repeated moves, 0 goes straight to the wall
+    pub das_cut: f32,          // a charged DAS waits this long after a rotation or a new piece
+    pub soft_drop_factor: f32, // soft drop is this many times faster than the gravity
+}
+
+impl Default for HandlingConfig {
+    fn default() -> Self {
+        Self {
+            das: 0.167,
+            arr: 0.033,
+            das_cut: 0.0,
+            soft_drop_factor: 20.0,
+        }
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            mode: ModeKind::default(),
+            board_width: 10,
+            board_height: 20,
+            randomizer: RandomizerKind::default(),
+            preview_count: 5,
+            ghost_piece: true,
+            lock_delay: 0.5,
+            lock_resets: 15,
+            gravity: GravityCurve::default(),
+            start_level: 1,
+            lines_per_level: 10,
+            twenty_g_level: Some(20),
+            player_name: "Player".to_string(),
+            records_file: None,
+            seed: None,
+            audio: AudioConfig::default(),
+        }
+    }
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            clear_sound: "audio/clear.ogg".to_string(),
+            drop_sound: "audio/drop1.ogg".to_string(),
+            music: vec![
+                "audio/Computer_Music_All-Stars_-_Wheres_My_Jetpack.ogg".to_string(),
+                "audio/Computer_Music_All-Stars_-_Albatross_v2.ogg".to_string(),
+            ],
+        }
+    }
+}
+
+/*
+A value that parsed fine but makes no sense for the game.
+The message says which file and which field, so whoever edits the config knows what to fix.
+*/
+#[derive(Debug)]
+pub struct ConfigError {
+    pub file: &'static str,
+    pub field: &'static str,
+    pub reason: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: `{}` {}", self.file, self.field, self.reason)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+// Fails with the reason when the condition does not hold.
+fn check(
+    ok: bool,
+    file: &'static str,
+    field: &'static str,
+    reason: impl FnOnce() -> String,
+) -> Result<(), ConfigError> {
+    if ok {
+        Ok(())
+    } else {
+        Err(ConfigError {
+            file,
+            field,
+            reason: reason(),
+        })
+    }
+}
+
+impl GameConfig {
+    pub const FILE: &'static str = "config/gameplay.ron";
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let file = Self::FILE;
+        if let ModeKind::Sprint { lines } = self.mode {
+            check(lines > 0, file, "mode", || {
+                "needs at least 1 line for a sprint".to_string()
+            })?;
+        }
+        if let ModeKind::Ultra { seconds } = self.mode {
+            check(seconds > 0, file, "mode", || {
+                "needs at least 1 second for ultra".to_string()
+            })?;
+        }
+        // The garbage has to leave some room on the board to play in.
+        if let ModeKind::Dig { rows, messiness } = self.mode {
+            check(rows > 0, file, "mode", || {
+                "needs at least 1 row of garbage to dig".to_string()
+            })?;
+            check((rows as usize) < self.board_height, file, "mode", || {
+                format!(
+                    "needs fewer garbage rows than the board is high, it has {}",
+                    rows
+                )
+            })?;
+            check(messiness <= 100, file, "mode", || {
+                format!("messiness is a percentage, it is {}", messiness)
+            })?;
+        }
+        check(
+            (4..=40).contains(&self.board_width),
+            file,
+            "board_width",
+            || format!("must be between 4 and 40, it is {}", self.board_width),
+        )?;
+        check(
+            (4..=100).contains(&self.board_height),
+            file,
+            "board_height",
+            || format!("must be between 4 and 100, it is {}", self.board_height),
+        )?;
+        // Only so many pieces fit in the side panel.
+        check(self.preview_count <= 5, file, "preview_count", || {
+            format!("can be 5 at most, it is {}", self.preview_count)
+        })?;
+        check(self.lock_delay >= 0.0, file, "lock_delay", || {
+            format!("cannot be negative, it is {}", self.lock_delay)
+        })?;
+        match &self.gravity {
+            GravityCurve::Guideline => {}
+            GravityCurve::Fixed(seconds) => {
+                check(*seconds > 0.0, file, "gravity", || {
+                    format!("needs more than 0 seconds per row, it is {}", seconds)
+                })?;
+            }
+            GravityCurve::Table(table) => {
+                check(!table.is_empty(), file, "gravity", || {
+                    "needs the seconds per row of at least one level".to_string()
+                })?;
+                for (index, seconds) in table.iter().enumerate() {
+                    check(*seconds > 0.0, file, "gravity", || {
+                        format!(
+                            "needs more than 0 seconds per row, level {} has {}",
+                            index + 1,
+                            seconds
+                        )
+                    })?;
+                }
+            }
+        }
+        check(self.start_level >= 1, file, "start_level", || {
+            "has to be at least 1".to_string()
+        })?;
+        check(self.lines_per_level >= 1, file, "lines_per_level", || {
+            "has to be at least 1".to_string()
+        })?;
+        // A marathon that is won before the first piece is no marathon.
+        match self.mode {
+            ModeKind::Marathon {
+                goal: MarathonGoal::Lines(lines),
+                ..
+            } => check(lines > 0, file, "mode", || {
+                "needs at least 1 line for a marathon".to_string()
+            })?,
+            ModeKind::Marathon {
+                goal: MarathonGoal::Level(level),
+                ..
+            } => check(level > self.start_level, file, "mode", || {
+                format!(
+                    "needs a goal above the start level {}, it is {}",
+                    self.start_level, level
+                )
+            })?,
+            _ => {}
+        }
+        check(!self.player_name.is_empty(), file, "player_name", || {
+            "is empty".to_string()
+        })?;
+        check(
+            !self.audio.clear_sound.is_empty(),
+            file,
+            "audio.clear_sound",
+            || "is empty".to_string(),
+        )?;
+        check(
+            !self.audio.drop_sound.is_empty(),
+            file,
+            "audio.drop_sound",
+            || "is empty".to_string(),
+        )?;
+        check(
+            self.audio.music.iter().all(|track| !track.is_empty()),
+            file,
+            "audio.music",
+            || "has an empty path".to_string(),
+        )
+    }
+}
+
+impl HandlingConfig {
+    pub const FILE: &'static str = "config/handling.ron";
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let file = Self::FILE;
+        for (field, value) in [
+            ("das", self.das),
+            ("arr", self.arr),
+            ("das_cut", self.das_cut),
+        ]
+        .iter()
+        {
+            check(*value >= 0.0, file, field, || {
+                format!("cannot be negative, it is {}", value)
+            })?;
+        }
+        check(
+            self.soft_drop_factor >= 1.0,
+            file,
+            "soft_drop_factor",
+            || format!("has to be at least 1, it is {}", self.soft_drop_factor),
+        )
+    }
+}
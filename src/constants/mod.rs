@@ -1,11 +0,0 @@
-pub const BOARD_WIDTH: u32 = 10;
-pub const BOARD_HEIGHT: u32 = 20;
-pub const FALL_TIMER: f32 = 0.9;
-
-pub const CLEAR_SOUND: &str = "audio/clear.ogg";
-pub const DROP_SOUND: &str = "audio/drop1.ogg";
-
-pub const MUSIC_TRACKS: &[&str] = &[
-    "audio/Computer_Music_All-Stars_-_Wheres_My_Jetpack.ogg",
-    "audio/Computer_Music_All-Stars_-_Albatross_v2.ogg",
-];
@@ -0,0 +1,198 @@
+use super::piece::{Piece, PieceType, Position};
+
+// What fills a cell, a block of a piece that landed or a block of garbage.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Cell {
+    Piece(PieceType),
+    Garbage,
+}
+
+/*
+The board is just a grid of cells, row 0 is the floor and col 0 is the left wall.
+Every cell remembers which piece it came from so we still know how to color it.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct Board {
+    width: usize,
+    height: usize,
+    cells: Vec<Option<Cell>>,
+}
+
+impl Board {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![None; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn index(&self, pos: &Position) -> Option<usize> {
+        if pos.row < 0 || pos.col < 0 {
+            return None;
+        }
+        let (row, col) = (pos.row as usize, pos.col as usize);
+        if row >= self.height || col >= self.width {
+            return None;
+        }
+        Some(row * self.width + col)
+    }
+
+    /// What is stored at this position, outside of the board is always empty.
+    pub fn get(&self, pos: &Position) -> Option<Cell> {
+        self.index(pos).and_then(|index| self.cells[index])
+    }
+
+    pub fn set(&mut self, pos: &Position, cell: Option<Cell>) {
+        if let Some(index) = self.index(pos) {
+            self.cells[index] = cell;
+        }
+    }
+
+    /// A cell is free when it is inside the walls and nothing has landed there.
+    pub fn is_free(&self, pos: &Position) -> bool {
+        self.index(pos)
+            .map(|index| self.cells[index].is_none())
+            .unwrap_or(false)
+    }
+
+    /*
+    This used to live in both the input system and the dropping system,
+    now everyone asks the board instead.
+    */
+    pub fn collides(&self, piece: &Piece, position: &Position) -> bool {
+        piece
+            .get_filled_positions(position)
+            .iter()
+            .any(|pos| !self.is_free(pos))
+    }
+
+    /// Writes the piece into the grid and returns the cells it took.
+    pub fn lock(&mut self, piece: &Piece, position: &Position) -> Vec<Position> {
+        let cells = piece.get_filled_positions(position);
+        for cell in &cells {
+            self.set(cell, Some(Cell::Piece(piece.piece_type)));
+        }
+        cells
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.iter().all(Option::is_none)
+    }
+
+    /// Pushes everything up a row and fills the floor with garbage, except for the hole.
+    pub fn push_garbage_row(&mut self, hole: usize) {
+        self.cells.truncate((self.height - 1) * self.width);
+        let row = (0..self.width)
+            .map(|col| {
+                if col == hole {
+                    None
+                } else {
+                    Some(Cell::Garbage)
+                }
+            })
+            .collect::<Vec<_>>();
+        self.cells.splice(0..0, row);
+    }
+
+    pub fn is_row_full(&self, row: usize) -> bool {
+        self.cells[row * self.width..(row + 1) * self.width]
+            .iter()
+            .all(Option::is_some)
+    }
+
+    /*
+    Remove every full row and let everything above fall down.
+    The rows we return are the ones before anything moved, from bottom to top.
+    */
+    pub fn clear_full_rows(&mut self) -> Vec<i16> {
+        let full_rows = (0..self.height)
+            .filter(|row| self.is_row_full(*row))
+            .collect::<Vec<_>>();
+
+        for row in full_rows.iter().rev() {
+            let start = row * self.width;
+            self.cells.drain(start..start + self.width);
+            self.cells.extend(vec![None; self.width]);
+        }
+
+        full_rows.into_iter().map(|row| row as i16).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill_row(board: &mut Board, row: i16) {
+        for col in 0..board.width() as i16 {
+            board.set(&Position { row, col }, Some(Cell::Garbage));
+        }
+    }
+
+    #[test]
+    fn collides_with_walls_and_floor() {
+        let board = Board::new(10, 20);
+        // The O is in cols 1 and 2 and the middle two rows of its box.
+        let piece = Piece::new(PieceType::O);
+        let at = |row, col| Position { row, col };
+
+        assert!(!board.collides(&piece, &at(0, -1)));
+        assert!(board.collides(&piece, &at(0, -2)));
+        assert!(!board.collides(&piece, &at(0, 7)));
+        assert!(board.collides(&piece, &at(0, 8)));
+        assert!(!board.collides(&piece, &at(-1, 0)));
+        assert!(board.collides(&piece, &at(-2, 0)));
+        assert!(!board.collides(&piece, &at(17, 0)));
+        assert!(board.collides(&piece, &at(18, 0)));
+    }
+
+    #[test]
+    fn collides_with_the_stack() {
+        let mut board = Board::new(10, 20);
+        board.set(&Position { row: 1, col: 2 }, Some(Cell::Garbage));
+        let piece = Piece::new(PieceType::O);
+
+        assert!(board.collides(&piece, &Position { row: 0, col: 0 }));
+        assert!(!board.collides(&piece, &Position { row: 2, col: 0 }));
+    }
+
+    #[test]
+    fn clearing_rows_moves_the_rows_above_down() {
+        let mut board = Board::new(10, 20);
+        fill_row(&mut board, 0);
+        board.set(
+            &Position { row: 1, col: 0 },
+            Some(Cell::Piece(PieceType::T)),
+        );
+        fill_row(&mut board, 2);
+        board.set(
+            &Position { row: 3, col: 5 },
+            Some(Cell::Piece(PieceType::S)),
+        );
+
+        assert_eq!(board.clear_full_rows(), vec![0, 2]);
+        assert_eq!(
+            board.get(&Position { row: 0, col: 0 }),
+            Some(Cell::Piece(PieceType::T))
+        );
+        assert_eq!(
+            board.get(&Position { row: 1, col: 5 }),
+            Some(Cell::Piece(PieceType::S))
+        );
+        assert_eq!(board.get(&Position { row: 3, col: 5 }), None);
+        assert!(!board.is_row_full(0));
+
+        board.set(&Position { row: 0, col: 0 }, None);
+        board.set(&Position { row: 1, col: 5 }, None);
+        assert!(board.is_empty());
+    }
+}
@@ -0,0 +1,389 @@
+use serde::{Deserialize, Serialize};
+
+use super::frame::ROW;
+use super::game::Game;
+use super::level::Gravity;
+
+// What the player holds down in one frame of the game, the controls only look at this.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PlayerInput {
+    pub direction: i16, // -1 is left, 1 is right
+    pub soft_drop: bool,
+    pub hard_drop: bool,
+    pub rotate_cw: bool,
+    pub rotate_ccw: bool,
+    pub hold: bool,
+}
+
+// How far the held direction moves the piece this frame.
+#[derive(Debug, PartialEq)]
+enum Shift {
+    Cols(i16),
+    ToWall(i16),
+}
+
+/*
+Holding left or right moves the piece once, waits for the DAS (delayed auto shift)
+and then keeps moving it every ARR (auto repeat rate) frames, an ARR of 0 goes straight to the wall.
+Everything is counted in frames.
+*/
+#[derive(Debug, Default)]
+struct AutoShift {
+    das: u32,
+    arr: u32,
+    das_cut: u32,
+    direction: i16,
+    das_timer: u32,
+    arr_timer: u32,
+}
+
+impl AutoShift {
+    fn new(das: u32, arr: u32, das_cut: u32) -> Self {
+        Self {
+            das,
+            arr,
+            das_cut,
+            ..Self::default()
+        }
+    }
+
+    fn update(&mut self, direction: i16) -> Shift {
+        if direction != self.direction {
+            self.direction = direction;
+            self.das_timer = self.das;
+            self.arr_timer = 0;
+            return Shift::Cols(direction);
+        }
+        if direction == 0 {
+            return Shift::Cols(0);
+        }
+
+        if self.das_timer > 0 {
+            self.das_timer -= 1;
+            if self.das_timer > 0 {
+                return Shift::Cols(0);
+            }
+            // Charged, the first repeat goes in the same frame.
+            self.arr_timer = 0;
+        } else {
+            self.arr_timer = self.arr_timer.saturating_sub(1);
+        }
+
+        if self.arr == 0 {
+            return Shift::ToWall(direction);
+        }
+        if self.arr_timer > 0 {
+            return Shift::Cols(0);
+        }
+        self.arr_timer = self.arr;
+        Shift::Cols(direction)
+    }
+
+    // DAS cut, a charged shift waits a bit before it carries on with the new piece or rotation.
+    fn cut(&mut self) {
+        if self.direction != 0 && self.das_timer == 0 {
+            self.das_timer = self.das_cut;
+        }
+    }
+}
+
+/*
+Turns the input of one frame into moves of the active piece, the piece input system calls it every frame
+with the keyboard or with the input of a replay. Nothing in here looks at the clock, so the same input
+on the same frames always plays the same game.
+*/
+pub struct Controls {
+    auto_shift: AutoShift,
+    soft_drop_factor: f32,
+    soft_drop_fall: Option<u32>, // None while soft drop is not held
+    held: PlayerInput,           // what was held down the last time we looked
+    pieces_locked: u32,
+}
+
+impl Controls {
+    // The DAS, ARR and DAS cut in frames, the soft drop as a multiple of the gravity.
+    pub fn new(das: u32, arr: u32, das_cut: u32, soft_drop_factor: f32) -> Self {
+        Self {
+            auto_shift: AutoShift::new(das, arr, das_cut),
+            soft_drop_factor,
+            soft_drop_fall: None,
+            held: PlayerInput::default(),
+            pieces_locked: 0,
+        }
+    }
+
+    /* Sometimes when user want to spam the button for faster action,
+    and we do not want them to break our game.
+    An action only goes off on the frame its key goes down, holding it does nothing more.
+    */
+    fn pressed(held: &mut bool, action: bool) -> bool {
+        let pressed = action && !*held;
+        *held = action;
+        pressed
+    }
+
+    /**
+    Remember when the for loop runs, it runs super fast! So the soft drop falls a part of a row every frame
+    like the gravity does, only sped up by the soft drop factor, and every whole row goes down.
+    The first row goes right away.
+    */
+    fn soft_drop(&mut self, game: &mut Game, gravity: Gravity) {
+        let factor = self.soft_drop_factor.max(1.0);
+        let speed = match gravity {
+            Gravity::Speed(speed) => (speed as f32 * factor) as u32,
+            Gravity::TwentyG => return,
+        };
+
+        let fall = self.soft_drop_fall.get_or_insert(ROW);
+        while *fall >= ROW {
+            if !game.soft_drop() {
+                // It carries on right away once there is room below again.
+                *fall = ROW;
+                break;
+            }
+            *fall -= ROW;
+        }
+        *fall = fall.saturating_add(speed);
+    }
+
+    /// Plays one frame of input, true if the piece went into the hold slot.
+    pub fn update(&mut self, game: &mut Game, input: &PlayerInput, gravity: Gravity) -> bool {
+        // nothing to move until the next piece comes in
+        if game.active().is_none() {
+            return false;
+        }
+
+        // A new piece came in since last time.
+        if game.pieces_locked() != self.pieces_locked {
+            self.pieces_locked = game.pieces_locked();
+            self.auto_shift.cut();
+        }
+
+        // Swap with the hold slot, the rest of the input waits for the new piece.
+        if Self::pressed(&mut self.held.hold, input.hold) && game.hold() {
+            self.auto_shift.cut();
+            return true;
+        }
+
+        // Make sure it is no spam, the hard drop. The piece is done after that, it locks on this frame.
+        if Self::pressed(&mut self.held.hard_drop, input.hard_drop) {
+            game.hard_drop();
+            return false;
+        }
+
+        // Make sure that the rotation is in order
+        let rotated = Self::pressed(&mut self.held.rotate_cw, input.rotate_cw);
+        let rotated_ccw = Self::pressed(&mut self.held.rotate_ccw, input.rotate_ccw);
+
+        // The game checks the collisions and wall kicks for us, if nothing fits the piece just stays where it is.
+        let rotated = if rotated {
+            game.rotate_cw()
+        } else if rotated_ccw {
+            game.rotate_ccw()
+        } else {
+            false
+        };
+        if rotated {
+            self.auto_shift.cut();
+        }
+
+        // Move it the way it is held.
+        match self.auto_shift.update(input.direction) {
+            Shift::Cols(cols) => {
+                for _ in 0..cols.abs() {
+                    if !game.shift(cols.signum()) {
+                        break;
+                    }
+                }
+            }
+            Shift::ToWall(direction) => while game.shift(direction) {},
+        }
+
+        if input.soft_drop {
+            self.soft_drop(game, gravity);
+        } else {
+            self.soft_drop_fall = None;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::level::{GravityCurve, Level};
+    use super::super::piece::Piece;
+    use super::super::queue::PieceQueue;
+    use super::super::randomizer::RandomizerKind;
+    use super::super::rng::SeededRng;
+    use super::super::score::Score;
+    use super::*;
+    use rand::Rng;
+
+    // The frames the piece moved on while the direction was held, starting from frame 0.
+    fn moves(auto_shift: &mut AutoShift, direction: i16, frames: u32) -> Vec<u32> {
+        (0..frames)
+            .filter(|_| auto_shift.update(direction) != Shift::Cols(0))
+            .collect()
+    }
+
+    #[test]
+    fn das_then_arr() {
+        let mut auto_shift = AutoShift::new(10, 2, 0);
+        assert_eq!(moves(&mut auto_shift, 1, 17), vec![0, 10, 12, 14, 16]);
+    }
+
+    #[test]
+    fn first_move_goes_right_away_and_repeats_on_the_charge_frame() {
+        let mut auto_shift = AutoShift::new(10, 2, 0);
+        assert_eq!(auto_shift.update(-1), Shift::Cols(-1));
+        for _ in 1..10 {
+            assert_eq!(auto_shift.update(-1), Shift::Cols(0));
+        }
+        assert_eq!(auto_shift.update(-1), Shift::Cols(-1));
+    }
+
+    #[test]
+    fn no_arr_goes_to_the_wall() {
+        let mut auto_shift = AutoShift::new(10, 0, 0);
+        assert_eq!(auto_shift.update(1), Shift::Cols(1));
+        for _ in 1..10 {
+            assert_eq!(auto_shift.update(1), Shift::Cols(0));
+        }
+        assert_eq!(auto_shift.update(1), Shift::ToWall(1));
+        assert_eq!(auto_shift.update(1), Shift::ToWall(1));
+    }
+
+    #[test]
+    fn no_das_repeats_from_the_next_frame() {
+        let mut auto_shift = AutoShift::new(0, 2, 0);
+        assert_eq!(moves(&mut auto_shift, 1, 6), vec![0, 1, 3, 5]);
+    }
+
+    #[test]
+    fn switching_direction_charges_again() {
+        let mut auto_shift = AutoShift::new(10, 2, 0);
+        moves(&mut auto_shift, 1, 15);
+        assert_eq!(moves(&mut auto_shift, -1, 13), vec![0, 10, 12]);
+
+        // Letting go stops it, and the next press starts over too.
+        assert_eq!(moves(&mut auto_shift, 0, 3), Vec::<u32>::new());
+        assert_eq!(moves(&mut auto_shift, -1, 11), vec![0, 10]);
+    }
+
+    #[test]
+    fn das_cut_only_holds_back_a_charged_shift() {
+        let mut auto_shift = AutoShift::new(10, 2, 3);
+        moves(&mut auto_shift, 1, 5);
+        // Not charged yet, it still charges on frame 10.
+        auto_shift.cut();
+        assert_eq!(moves(&mut auto_shift, 1, 6), vec![5]);
+
+        // Charged, the cut waits 3 frames and then the repeats go on.
+        auto_shift.cut();
+        assert_eq!(moves(&mut auto_shift, 1, 5), vec![2, 4]);
+
+        // Nothing held, nothing to cut.
+        let mut auto_shift = AutoShift::new(10, 2, 3);
+        auto_shift.cut();
+        assert_eq!(auto_shift.das_timer, 0);
+    }
+
+    // A few minutes of someone mashing keys, written down like a replay does, only the changes.
+    fn recorded_inputs(seed: u64, frames: u64) -> Vec<(u64, PlayerInput)> {
+        let mut rng = SeededRng::new(seed);
+        let mut inputs = Vec::new();
+        for frame in 0..frames {
+            if rng.gen_range(0, 8) != 0 {
+                continue;
+            }
+            let input = PlayerInput {
+                direction: rng.gen_range(-1, 2),
+                soft_drop: rng.gen_range(0, 4) == 0,
+                hard_drop: rng.gen_range(0, 6) == 0,
+                rotate_cw: rng.gen(),
+                rotate_ccw: rng.gen_range(0, 4) == 0,
+                hold: rng.gen_range(0, 10) == 0,
+            };
+            inputs.push((frame, input));
+        }
+        inputs
+    }
+
+    // The frames in the order the gameplay systems run them: input, gravity, spawn, score.
+    fn play(
+        seed: u64,
+        inputs: &[(u64, PlayerInput)],
+        frames: u64,
+    ) -> (Game, Score, Level, PieceQueue) {
+        let mut game = Game::new(10, 20).with_lock_delay(30, 15);
+        let mut controls = Controls::new(10, 2, 1, 20.0);
+        let mut level = Level::new(1, 10, GravityCurve::Guideline, Some(20));
+        let mut score = Score::default();
+        let mut rng = SeededRng::new(seed);
+        let mut randomizer = RandomizerKind::SevenBag.build();
+        let mut queue = PieceQueue::default();
+        let mut generate = || randomizer.next_piece(&mut rng);
+        game.spawn(Piece::new(queue.next(5, &mut generate)));
+
+        let mut input = PlayerInput::default();
+        let mut next = 0;
+        for frame in 0..frames {
+            while let Some((at, recorded)) = inputs.get(next) {
+                if *at > frame {
+                    break;
+                }
+                input = *recorded;
+                next += 1;
+            }
+
+            let held = controls.update(&mut game, &input, level.gravity());
+            game.set_gravity(level.gravity());
+            let landing = game.tick();
+            if landing.is_some() || (held && game.active().is_none()) {
+                game.spawn(Piece::new(queue.next(5, &mut generate)));
+            }
+            queue.fill(5, &mut generate);
+            if let Some(landing) = landing {
+                score.add_drop(landing.soft_drop_rows, landing.hard_drop_rows);
+                score.add_landing(
+                    landing.cleared_rows.len(),
+                    landing.t_spin,
+                    landing.perfect_clear,
+                    level.level,
+                );
+                level.add_lines(landing.cleared_rows.len() as u32);
+            }
+            if game.top_out().is_some() {
+                break;
+            }
+        }
+        (game, score, level, queue)
+    }
+
+    #[test]
+    fn a_recorded_game_plays_the_same_every_time() {
+        let frames = 60 * 60 * 3;
+        let inputs = recorded_inputs(7, frames);
+        let (game, score, level, queue) = play(1234, &inputs, frames);
+        let (again, again_score, again_level, again_queue) = play(1234, &inputs, frames);
+
+        // Something has to happen for this to mean anything.
+        assert!(game.pieces_locked() > 20);
+        assert!(score.points > 0);
+
+        assert_eq!(game.board, again.board);
+        assert_eq!(game.active(), again.active());
+        assert_eq!(game.held(), again.held());
+        assert_eq!(game.top_out(), again.top_out());
+        assert_eq!(game.pieces_locked(), again.pieces_locked());
+        assert_eq!(game.lines_cleared(), again.lines_cleared());
+        assert_eq!(score, again_score);
+        assert_eq!(level.level, again_level.level);
+        assert!(queue.iter().eq(again_queue.iter()));
+
+        // Another seed is another game with the same keys.
+        let (other, _, _, _) = play(4321, &inputs, frames);
+        assert_ne!(game.board, other.board);
+    }
+}
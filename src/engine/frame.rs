@@ -0,0 +1,25 @@
+use std::time::Duration;
+
+// The game moves on in frames of the same length, however fast the screen is drawn.
+pub const FRAMES_PER_SECOND: u32 = 60;
+
+// Gravity moves the piece a part of a row every frame, this many parts make a whole row.
+pub const ROW: u32 = 65_536;
+
+// Nothing falls faster than this, 20 rows every frame is already the whole board.
+const MAX_SPEED: u32 = 20 * ROW;
+
+pub fn frame_time() -> Duration {
+    Duration::from_secs(1) / FRAMES_PER_SECOND
+}
+
+// The configs are in seconds, the game counts them in whole frames.
+pub fn frames(seconds: f32) -> u32 {
+    (seconds * FRAMES_PER_SECOND as f32).round().max(0.0) as u32
+}
+
+// How much of a row the piece falls every frame, when it falls a row every so many seconds.
+pub fn fall_speed(seconds: f32) -> u32 {
+    let speed = ROW as f32 / (seconds * FRAMES_PER_SECOND as f32);
+    (speed.round() as u32).clamp(1, MAX_SPEED)
+}
@@ -0,0 +1,564 @@
+use super::board::Board;
+use super::frame::{FRAMES_PER_SECOND, ROW};
+use super::kicks::kicks;
+use super::level::Gravity;
+use super::piece::{Piece, PieceType, Position};
+use super::t_spin::{self, TSpin};
+
+// The piece that the player is controlling right now.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ActivePiece {
+    pub piece: Piece,
+    pub position: Position,
+}
+
+// The last thing that happened to the active piece, a T-spin only counts if it was a rotation.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Action {
+    Spawn,
+    Move,
+    Rotate { kick: usize }, // which offset of the kick table made it fit
+}
+
+// What happened when a piece has landed, the systems use it to update the screen.
+#[derive(Clone, Debug)]
+pub struct Landing {
+    pub piece_type: PieceType,
+    pub cells: Vec<Position>,
+    pub cleared_rows: Vec<i16>,
+    pub soft_drop_rows: u32,
+    pub hard_drop_rows: u32,
+    pub perfect_clear: bool, // nothing is left on the board
+    pub t_spin: TSpin,
+}
+
+// Why the game ended.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TopOut {
+    BlockOut, // a new piece came in on top of the stack
+    LockOut,  // a piece locked completely above the visible field
+}
+
+// Rows above the visible field, pieces spawn in there and a high stack can grow into it.
+pub const BUFFER_ROWS: usize = 20;
+
+/**
+This is the whole game without amethyst, no World and no window.
+The systems only translate input and time into calls here and draw whatever comes out,
+so the rules can be played from a plain test or a simulation.
+*/
+pub struct Game {
+    pub board: Board,
+    visible_height: usize,
+    top_out: Option<TopOut>,
+    pieces_locked: u32,
+    lines_cleared: u32,
+    active: Option<ActivePiece>,
+    last_action: Action,
+    held: Option<PieceType>,
+    can_hold: bool,
+    gravity: Gravity,
+    fall: u32,       // Parts of a row fallen since the last step down
+    lock_delay: u32, // Frames a piece can rest on the stack before it locks
+    lock_timer: u32, // Frames until the resting piece locks
+    max_lock_resets: u32,
+    lock_resets: u32,
+    lowest_row: i16,
+    hard_dropped: bool,
+    soft_drop_rows: u32, // Rows the player dropped the active piece by hand, they are worth points
+    hard_drop_rows: u32,
+}
+
+impl Game {
+    // The height is what the player sees, the board is taller by the buffer.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            board: Board::new(width, height + BUFFER_ROWS),
+            visible_height: height,
+            top_out: None,
+            pieces_locked: 0,
+            lines_cleared: 0,
+            active: None,
+            last_action: Action::Spawn,
+            held: None,
+            can_hold: true,
+            gravity: Gravity::Speed(ROW / FRAMES_PER_SECOND),
+            fall: 0,
+            lock_delay: 30,
+            lock_timer: 30,
+            max_lock_resets: 15,
+            lock_resets: 0,
+            lowest_row: 0,
+            hard_dropped: false,
+            soft_drop_rows: 0,
+            hard_drop_rows: 0,
+        }
+    }
+
+    // The guideline gives 30 frames and 15 resets, but it is up to the config.
+    pub fn with_lock_delay(mut self, frames: u32, max_resets: u32) -> Self {
+        self.lock_delay = frames;
+        self.lock_timer = frames;
+        self.max_lock_resets = max_resets;
+        self
+    }
+
+    /*
+    The level decides the gravity, it can change in the middle of a fall.
+    The part of the row the piece already fell is kept, it goes on at the new speed.
+    */
+    pub fn set_gravity(&mut self, gravity: Gravity) {
+        self.gravity = gravity;
+    }
+
+    pub fn active(&self) -> Option<&ActivePiece> {
+        self.active.as_ref()
+    }
+
+    pub fn visible_height(&self) -> usize {
+        self.visible_height
+    }
+
+    pub fn top_out(&self) -> Option<TopOut> {
+        self.top_out
+    }
+
+    pub fn pieces_locked(&self) -> u32 {
+        self.pieces_locked
+    }
+
+    pub fn lines_cleared(&self) -> u32 {
+        self.lines_cleared
+    }
+
+    pub fn held(&self) -> Option<PieceType> {
+        self.held
+    }
+
+    pub fn can_hold(&self) -> bool {
+        self.can_hold
+    }
+
+    // Pieces come in right above the visible field in the middle, they take up the first two hidden rows.
+    pub fn spawn_position(&self) -> Position {
+        Position {
+            row: self.visible_height as i16 - 1,
+            col: (self.board.width() as i16 - 4) / 2,
+        }
+    }
+
+    /// Brings in a new piece, if it does not fit the game is over.
+    pub fn spawn(&mut self, piece: Piece) -> bool {
+        if self.top_out.is_some() {
+            return false;
+        }
+
+        let position = self.spawn_position();
+        if self.board.collides(&piece, &position) {
+            self.active = None;
+            self.top_out = Some(TopOut::BlockOut);
+            return false;
+        }
+
+        self.active = Some(ActivePiece { piece, position });
+        self.last_action = Action::Spawn;
+        self.fall = 0;
+        self.lock_timer = self.lock_delay;
+        self.lock_resets = 0;
+        self.lowest_row = position.row;
+        self.hard_dropped = false;
+        self.soft_drop_rows = 0;
+        self.hard_drop_rows = 0;
+        if self.gravity == Gravity::TwentyG {
+            self.sink();
+        }
+        true
+    }
+
+    /// The active piece is resting on the floor or on the stack.
+    pub fn on_ground(&self) -> bool {
+        match self.active {
+            Some(active) => {
+                let below = Position {
+                    row: active.position.row - 1,
+                    col: active.position.col,
+                };
+                self.board.collides(&active.piece, &below)
+            }
+            None => false,
+        }
+    }
+
+    // Every new lowest row gives back the whole lock delay and all of the resets.
+    fn update_lowest_row(&mut self) {
+        if let Some(active) = self.active {
+            if active.position.row < self.lowest_row {
+                self.lowest_row = active.position.row;
+                self.lock_timer = self.lock_delay;
+                self.lock_resets = 0;
+            }
+        }
+    }
+
+    /*
+    A successful move or rotation while the lock delay is running starts it over,
+    but only so many times, otherwise the player could spin the piece forever.
+    */
+    fn reset_lock_delay(&mut self) {
+        self.update_lowest_row();
+        if self.lock_timer < self.lock_delay && self.lock_resets < self.max_lock_resets {
+            self.lock_timer = self.lock_delay;
+            self.lock_resets += 1;
+        }
+    }
+
+    // Try to put the active piece somewhere else, nothing changes if it does not fit.
+    // After a hard drop the piece stays where it landed, it only waits for the lock.
+    fn try_place(&mut self, piece: Piece, position: Position) -> bool {
+        if self.hard_dropped {
+            return false;
+        }
+        match self.active {
+            Some(ref mut active) if !self.board.collides(&piece, &position) => {
+                active.piece = piece;
+                active.position = position;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn try_offset(&mut self, rows: i16, cols: i16) -> bool {
+        match self.active {
+            Some(active) => {
+                let position = Position {
+                    row: active.position.row + rows,
+                    col: active.position.col + cols,
+                };
+                let moved = self.try_place(active.piece, position);
+                if moved {
+                    self.last_action = Action::Move;
+                }
+                moved
+            }
+            None => false,
+        }
+    }
+
+    pub fn shift(&mut self, cols: i16) -> bool {
+        let moved = self.try_offset(0, cols);
+        if moved {
+            self.reset_lock_delay();
+        }
+        moved
+    }
+
+    // Moving down by hand also starts the fall over.
+    pub fn soft_drop(&mut self) -> bool {
+        let moved = self.try_offset(-1, 0);
+        if moved {
+            self.fall = 0;
+            self.soft_drop_rows += 1;
+            self.update_lowest_row();
+        }
+        moved
+    }
+
+    /*
+    Rotation uses the SRS kicks, if the piece does not fit where it is we try the offsets in order.
+    This is what lets the player rotate against a wall or tuck a piece under an overhang.
+    */
+    fn rotate(&mut self, clockwise: bool) -> bool {
+        let active = match self.active {
+            Some(active) => active,
+            None => return false,
+        };
+
+        let mut piece = active.piece;
+        if clockwise {
+            piece.rotate_cw();
+        } else {
+            piece.rotate_ccw();
+        }
+
+        let kick = kicks(piece.piece_type, active.piece.rotation, piece.rotation)
+            .iter()
+            .position(|(cols, rows)| {
+                let position = Position {
+                    row: active.position.row + rows,
+                    col: active.position.col + cols,
+                };
+                self.try_place(piece, position)
+            });
+        match kick {
+            Some(kick) => {
+                self.last_action = Action::Rotate { kick };
+                self.reset_lock_delay();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn rotate_cw(&mut self) -> bool {
+        self.rotate(true)
+    }
+
+    pub fn rotate_ccw(&mut self) -> bool {
+        self.rotate(false)
+    }
+
+    /*
+    Hold puts the active piece aside and brings back the one that was held before.
+    If nothing was held yet, the active piece is gone and the spawner has to bring the next one.
+    It only works once until the piece lands, otherwise the player could stall forever.
+    */
+    pub fn hold(&mut self) -> bool {
+        if !self.can_hold || self.hard_dropped {
+            return false;
+        }
+        let active = match self.active.take() {
+            Some(active) => active,
+            None => return false,
+        };
+
+        if let Some(held) = self.held.replace(active.piece.piece_type) {
+            self.spawn(Piece::new(held));
+        }
+        self.can_hold = false;
+        true
+    }
+
+    /// Where the active piece would end up if it fell straight down.
+    pub fn drop_position(&self) -> Option<Position> {
+        self.active.map(|active| {
+            let mut position = active.position;
+            loop {
+                let below = Position {
+                    row: position.row - 1,
+                    col: position.col,
+                };
+                if self.board.collides(&active.piece, &below) {
+                    return position;
+                }
+                position = below;
+            }
+        })
+    }
+
+    // With 20G the piece goes straight down to the floor, it is not a hard drop so it does not lock yet.
+    fn sink(&mut self) {
+        if let Some(position) = self.drop_position() {
+            if let Some(ref mut active) = self.active {
+                if active.position != position {
+                    active.position = position;
+                    self.last_action = Action::Move;
+                }
+            }
+        }
+        self.update_lowest_row();
+    }
+
+    /*
+    Hard drop sends the piece down to the floor immediately, it skips the lock delay and locks on the next tick.
+    Until then it cannot be moved, rotated or held anymore, so it cannot sneak away from where it landed.
+    */
+    pub fn hard_drop(&mut self) {
+        if let Some(position) = self.drop_position() {
+            if let Some(ref mut active) = self.active {
+                // Dropping straight from a spin into place keeps the spin.
+                if active.position != position {
+                    self.hard_drop_rows += (active.position.row - position.row) as u32;
+                    active.position = position;
+                    self.last_action = Action::Move;
+                }
+                self.hard_dropped = true;
+            }
+        }
+    }
+
+    /*
+    Gravity, one frame of it. Every frame the piece falls a part of a row, and every whole row it goes one row down,
+    on the high levels that can be several rows in one frame.
+    Once it rests on something the lock delay counts down instead, and when that runs out the piece lands.
+    Full rows are cleared right away.
+    */
+    pub fn tick(&mut self) -> Option<Landing> {
+        if self.active.is_none() || self.top_out.is_some() {
+            return None;
+        }
+
+        // A hard dropped piece only locks where it really rests, otherwise it goes on falling like any other.
+        if self.hard_dropped {
+            if self.on_ground() {
+                return self.lock();
+            }
+            self.hard_dropped = false;
+        }
+
+        if self.gravity == Gravity::TwentyG {
+            self.sink();
+        }
+
+        if self.on_ground() {
+            self.lock_timer = self.lock_timer.saturating_sub(1);
+            if self.lock_timer == 0 {
+                return self.lock();
+            }
+            return None;
+        }
+
+        if let Gravity::Speed(speed) = self.gravity {
+            self.fall += speed;
+        }
+        while self.fall >= ROW {
+            self.fall -= ROW;
+            if !self.try_offset(-1, 0) {
+                self.fall = 0;
+                break;
+            }
+        }
+        self.update_lowest_row();
+        None
+    }
+
+    fn lock(&mut self) -> Option<Landing> {
+        let active = self.active.take()?;
+        let t_spin = match self.last_action {
+            Action::Rotate { kick } => t_spin::detect(&self.board, &active, kick),
+            _ => TSpin::None,
+        };
+        let cells = self.board.lock(&active.piece, &active.position);
+        if cells
+            .iter()
+            .all(|cell| cell.row >= self.visible_height as i16)
+        {
+            self.top_out = Some(TopOut::LockOut);
+        }
+
+        let cleared_rows = self.board.clear_full_rows();
+        self.can_hold = true;
+        self.pieces_locked += 1;
+        self.lines_cleared += cleared_rows.len() as u32;
+
+        Some(Landing {
+            piece_type: active.piece.piece_type,
+            cells,
+            cleared_rows,
+            soft_drop_rows: self.soft_drop_rows,
+            hard_drop_rows: self.hard_drop_rows,
+            perfect_clear: self.board.is_empty(),
+            t_spin,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::board::Cell;
+    use super::*;
+
+    #[test]
+    fn tick_locks_a_piece_after_the_lock_delay() {
+        let mut game = Game::new(10, 20).with_lock_delay(3, 15);
+        assert!(game.spawn(Piece::new(PieceType::O)));
+        while game.soft_drop() {}
+        assert!(game.on_ground());
+
+        assert!(game.tick().is_none());
+        assert!(game.tick().is_none());
+        let landing = game.tick().expect("the piece should lock");
+        assert_eq!(landing.piece_type, PieceType::O);
+        assert_eq!(landing.soft_drop_rows, 20);
+        assert!(landing.cells.iter().all(|cell| cell.row <= 1));
+        assert!(game.active().is_none());
+        assert_eq!(game.pieces_locked(), 1);
+
+        // The next piece comes in at the top and starts falling again.
+        assert!(game.spawn(Piece::new(PieceType::T)));
+        let active = game.active().unwrap();
+        assert_eq!(active.position, game.spawn_position());
+        assert!(!game.on_ground());
+    }
+
+    #[test]
+    fn gravity_moves_the_piece_down() {
+        let mut game = Game::new(10, 20);
+        game.set_gravity(Gravity::Speed(ROW / 2));
+        game.spawn(Piece::new(PieceType::T));
+        let row = game.active().unwrap().position.row;
+
+        game.tick();
+        assert_eq!(game.active().unwrap().position.row, row);
+        game.tick();
+        assert_eq!(game.active().unwrap().position.row, row - 1);
+    }
+
+    #[test]
+    fn locking_clears_full_rows() {
+        let mut game = Game::new(10, 20);
+        for col in 0..8 {
+            game.board
+                .set(&Position { row: 0, col }, Some(Cell::Garbage));
+        }
+        // An O in the last two columns fills up the floor row.
+        game.spawn(Piece::new(PieceType::O));
+        while game.shift(1) {}
+        game.hard_drop();
+        let landing = game.tick().unwrap();
+
+        assert_eq!(landing.cleared_rows, vec![0]);
+        assert_eq!(game.lines_cleared(), 1);
+        assert!(!game.board.is_empty());
+    }
+    #[test]
+    fn hard_dropped_piece_stays_put_until_it_locks() {
+        let mut game = Game::new(10, 20);
+        game.spawn(Piece::new(PieceType::T));
+        game.hard_drop();
+        let dropped = *game.active().unwrap();
+
+        assert!(!game.shift(1));
+        assert!(!game.rotate_cw());
+        assert!(!game.soft_drop());
+        assert!(!game.hold());
+        assert_eq!(game.active().unwrap().position, dropped.position);
+        assert_eq!(game.active().unwrap().piece, dropped.piece);
+
+        let landing = game.tick().unwrap();
+        assert_eq!(landing.t_spin, TSpin::None);
+        assert_eq!(
+            landing.cells,
+            dropped.piece.get_filled_positions(&dropped.position)
+        );
+        assert_eq!(game.held(), None);
+    }
+    #[test]
+    fn every_piece_spawns_above_the_visible_field() {
+        for piece_type in PieceType::ALL.iter() {
+            let mut game = Game::new(10, 20);
+            assert!(game.spawn(Piece::new(*piece_type)));
+            let active = game.active().unwrap();
+            let cells = active.piece.get_filled_positions(&active.position);
+            assert!(
+                cells.iter().all(|cell| cell.row >= 20),
+                "{:?} spawns at {:?}",
+                piece_type,
+                cells
+            );
+            assert!(cells.iter().all(|cell| cell.row <= 21));
+        }
+    }
+
+    #[test]
+    fn a_stack_right_below_the_buffer_does_not_block_out() {
+        for piece_type in PieceType::ALL.iter() {
+            let mut game = Game::new(10, 20);
+            for col in 0..10 {
+                game.board
+                    .set(&Position { row: 19, col }, Some(Cell::Garbage));
+            }
+            assert!(game.spawn(Piece::new(*piece_type)), "{:?}", piece_type);
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use rand::{Rng, RngCore};
+
+/*
+Where the hole of every garbage row goes, from the floor up.
+Messiness is the chance in percent that the hole moves to another column from one row to the next,
+0 makes one straight well to dig down and 100 moves it on every row.
+*/
+pub fn garbage_holes(rows: u32, width: usize, messiness: u32, rng: &mut dyn RngCore) -> Vec<usize> {
+    let mut holes = Vec::new();
+    let mut hole = rng.gen_range(0, width);
+    for _ in 0..rows {
+        if !holes.is_empty() && width > 1 && rng.gen_range(0, 100) < messiness {
+            // Anywhere but the same column.
+            hole = (hole + rng.gen_range(1, width)) % width;
+        }
+        holes.push(hole);
+    }
+    holes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::rng::SeededRng;
+    use super::*;
+
+    #[test]
+    fn no_messiness_is_one_straight_well() {
+        for seed in 0..20 {
+            let holes = garbage_holes(10, 10, 0, &mut SeededRng::new(seed));
+            assert_eq!(holes.len(), 10);
+            assert!(holes.iter().all(|hole| *hole == holes[0] && *hole < 10));
+        }
+    }
+
+    #[test]
+    fn full_messiness_moves_the_hole_on_every_row() {
+        for seed in 0..20 {
+            let holes = garbage_holes(50, 10, 100, &mut SeededRng::new(seed));
+            assert!(holes.windows(2).all(|pair| pair[0] != pair[1]));
+            assert!(holes.iter().all(|hole| *hole < 10));
+        }
+    }
+
+    #[test]
+    fn the_same_seed_digs_the_same_holes() {
+        let holes = garbage_holes(20, 10, 40, &mut SeededRng::new(3));
+        assert_eq!(holes, garbage_holes(20, 10, 40, &mut SeededRng::new(3)));
+    }
+}
@@ -0,0 +1,131 @@
+use super::piece::PieceType;
+
+/*
+Super Rotation System wall kicks, https://tetris.fandom.com/wiki/SRS
+When a rotation does not fit, we try to move the piece by these offsets one by one
+and take the first one that fits. Offsets are (col, row) and a positive row means up.
+Rotation states are 0 (spawn), 1 (R), 2 and 3 (L).
+*/
+type Kicks = [(i16, i16); 5];
+
+const JLSTZ_KICKS: [Kicks; 8] = [
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)], // 0 -> R
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],     // R -> 0
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],     // R -> 2
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)], // 2 -> R
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],    // 2 -> L
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],  // L -> 2
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],  // L -> 0
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],    // 0 -> L
+];
+
+// The I piece is longer so it gets its own table.
+const I_KICKS: [Kicks; 8] = [
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)], // 0 -> R
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)], // R -> 0
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)], // R -> 2
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)], // 2 -> R
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)], // 2 -> L
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)], // L -> 2
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)], // L -> 0
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)], // 0 -> L
+];
+
+// O looks the same in every rotation, so it never kicks.
+const NO_KICKS: [(i16, i16); 1] = [(0, 0)];
+
+/// Offsets to try, in order, when rotating from one state to the next.
+pub fn kicks(piece_type: PieceType, from: u8, to: u8) -> &'static [(i16, i16)] {
+    let (from, to) = (from % 4, to % 4);
+    let index = match (from, to) {
+        (0, 1) => 0,
+        (1, 0) => 1,
+        (1, 2) => 2,
+        (2, 1) => 3,
+        (2, 3) => 4,
+        (3, 2) => 5,
+        (3, 0) => 6,
+        (0, 3) => 7,
+        _ => return &NO_KICKS,
+    };
+
+    match piece_type {
+        PieceType::O => &NO_KICKS,
+        PieceType::I => &I_KICKS[index],
+        _ => &JLSTZ_KICKS[index],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::board::Cell;
+    use super::super::game::Game;
+    use super::super::piece::{Piece, Position};
+    use super::*;
+
+    // Which offset of the table took the piece from one position to the other.
+    fn kick_used(piece_type: PieceType, from: u8, to: u8, old: Position, new: Position) -> usize {
+        let offset = (new.col - old.col, new.row - old.row);
+        kicks(piece_type, from, to)
+            .iter()
+            .position(|kick| *kick == offset)
+            .expect("the piece moved by an offset that is not in the table")
+    }
+
+    #[test]
+    fn o_never_kicks() {
+        assert_eq!(kicks(PieceType::O, 0, 1), &[(0, 0)]);
+        assert_eq!(kicks(PieceType::T, 0, 2), &[(0, 0)]);
+    }
+
+    #[test]
+    fn i_kicks_off_the_left_wall() {
+        let mut game = Game::new(10, 20);
+        game.spawn(Piece::new(PieceType::I));
+        assert!(game.rotate_cw());
+        while game.shift(-1) {}
+        // Standing up against the wall, the I is in col 2 of its box.
+        let before = game.active().unwrap().position;
+        assert_eq!(before.col, -2);
+
+        assert!(game.rotate_ccw());
+        let after = *game.active().unwrap();
+        assert_eq!(after.piece.rotation, 0);
+        assert_eq!(
+            after.position,
+            Position {
+                row: before.row,
+                col: 0
+            }
+        );
+        assert_eq!(kick_used(PieceType::I, 1, 0, before, after.position), 1);
+    }
+
+    #[test]
+    fn t_tucks_under_an_overhang() {
+        let mut game = Game::new(10, 20);
+        for (row, col) in [(0, 2), (2, 1), (2, 4), (2, 5)].iter() {
+            game.board.set(
+                &Position {
+                    row: *row,
+                    col: *col,
+                },
+                Some(Cell::Garbage),
+            );
+        }
+        game.spawn(Piece::new(PieceType::T));
+        assert!(game.rotate_cw());
+        assert!(game.shift(-3));
+        while game.soft_drop() {}
+        let before = game.active().unwrap().position;
+        assert_eq!(before, Position { row: 1, col: 0 });
+
+        // Pointing down it does not fit in place or one to the right, it has to go under the overhang.
+        assert!(game.rotate_cw());
+        let after = *game.active().unwrap();
+        assert_eq!(after.piece.rotation, 2);
+        assert_eq!(after.position, Position { row: 0, col: 1 });
+        assert_eq!(kick_used(PieceType::T, 1, 2, before, after.position), 2);
+        assert!(game.on_ground());
+    }
+}
@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+
+use super::frame::fall_speed;
+
+// How fast the active piece falls.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gravity {
+    Speed(u32), // Parts of a row it falls every frame, a whole row is ROW of them
+    TwentyG,    // The piece is always on the floor, it appears there already
+}
+
+/*
+The guideline curve, https://tetris.fandom.com/wiki/Marathon
+Level 1 falls a row every second, and by level 15 it is already more than a row per frame.
+The curve is only meant to go to level 20, way past that the base would go below 0, so it stops at 0 (as fast as it gets).
+*/
+pub fn guideline_gravity(level: u32) -> f32 {
+    let steps = level.saturating_sub(1);
+    (0.8 - steps as f32 * 0.007).max(0.0).powi(steps as i32)
+}
+
+// Where the gravity of each level comes from.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub enum GravityCurve {
+    #[default]
+    Guideline,
+    Fixed(f32),      // Seconds between each step down, on every level
+    Table(Vec<f32>), // Seconds between each step down from level 1 on, the last one goes for every level after it
+}
+
+/// The level goes up every few cleared lines, and the pieces fall faster with it.
+#[derive(Clone, Debug)]
+pub struct Level {
+    pub level: u32,
+    lines_per_level: u32,
+    lines_in_level: u32,
+    curve: GravityCurve,
+    twenty_g_level: Option<u32>,
+}
+
+impl Level {
+    pub fn new(
+        start_level: u32,
+        lines_per_level: u32,
+        curve: GravityCurve,
+        twenty_g_level: Option<u32>,
+    ) -> Self {
+        Self {
+            level: start_level.max(1),
+            lines_per_level: lines_per_level.max(1),
+            lines_in_level: 0,
+            curve,
+            twenty_g_level,
+        }
+    }
+
+    // Lines needed until the next level.
+    pub fn lines_to_next(&self) -> u32 {
+        self.lines_per_level - self.lines_in_level
+    }
+
+    /// Counts the cleared lines, true if that was enough for a new level.
+    pub fn add_lines(&mut self, lines: u32) -> bool {
+        let before = self.level;
+        self.lines_in_level += lines;
+        while self.lines_in_level >= self.lines_per_level {
+            self.lines_in_level -= self.lines_per_level;
+            self.level += 1;
+        }
+        self.level != before
+    }
+
+    pub fn gravity(&self) -> Gravity {
+        if self.twenty_g_level.is_some_and(|level| self.level >= level) {
+            return Gravity::TwentyG;
+        }
+        match &self.curve {
+            GravityCurve::Guideline => Gravity::Speed(fall_speed(guideline_gravity(self.level))),
+            GravityCurve::Fixed(seconds) => Gravity::Speed(fall_speed(*seconds)),
+            GravityCurve::Table(table) => {
+                let index = (self.level as usize - 1).min(table.len() - 1);
+                Gravity::Speed(fall_speed(table[index]))
+            }
+        }
+    }
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Self::new(1, 10, GravityCurve::Guideline, Some(20))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guideline_keeps_getting_faster() {
+        let speed = |level| {
+            let level = Level::new(level, 10, GravityCurve::Guideline, None);
+            match level.gravity() {
+                Gravity::Speed(speed) => speed,
+                Gravity::TwentyG => panic!("20G is off"),
+            }
+        };
+        assert!(speed(1) < speed(10));
+        assert!(speed(10) < speed(20));
+        assert!(speed(20) <= speed(116));
+        assert!(speed(116) <= speed(200));
+    }
+    #[test]
+    fn twenty_g_only_when_it_is_on() {
+        let mut level = Level::new(19, 10, GravityCurve::Fixed(0.9), Some(20));
+        assert_ne!(level.gravity(), Gravity::TwentyG);
+        level.add_lines(10);
+        assert_eq!(level.gravity(), Gravity::TwentyG);
+
+        let mut level = Level::new(19, 10, GravityCurve::Fixed(0.9), None);
+        level.add_lines(1000);
+        assert_eq!(level.gravity(), Gravity::Speed(fall_speed(0.9)));
+    }
+}
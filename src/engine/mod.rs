@@ -0,0 +1,30 @@
+// The rules of the game live here, nothing in this module knows about amethyst.
+mod board;
+mod controls;
+mod frame;
+mod game;
+mod garbage;
+mod kicks;
+mod level;
+mod mode;
+mod piece;
+mod queue;
+mod randomizer;
+mod rng;
+mod score;
+mod t_spin;
+
+pub use self::{
+    board::{Board, Cell},
+    controls::{Controls, PlayerInput},
+    frame::{frame_time, frames, ROW},
+    game::{Action, ActivePiece, Game, Landing, TopOut},
+    level::{Gravity, GravityCurve, Level},
+    mode::{format_time, Ending, MarathonGoal, ModeKind, Session},
+    piece::{Piece, PieceType, Position},
+    queue::PieceQueue,
+    randomizer::{Randomizer, RandomizerKind},
+    rng::SeededRng,
+    score::Score,
+    t_spin::TSpin,
+};
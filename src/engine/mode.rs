@@ -0,0 +1,437 @@
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+use super::frame::FRAMES_PER_SECOND;
+use super::game::{Game, TopOut};
+use super::garbage::garbage_holes;
+
+/*
+What makes one mode different from another. Every mode ends on a top out,
+on top of that a mode can have a goal, a time limit, or both.
+A mode can also prepare the board before the first piece comes in,
+and let the game go on after the goal is reached.
+*/
+pub trait Mode: Send + Sync {
+    fn setup(&self, _game: &mut Game, _rng: &mut dyn RngCore) {}
+
+    fn goal_reached(&self, _progress: &Progress) -> bool {
+        false
+    }
+
+    fn time_limit(&self) -> Option<Duration> {
+        None
+    }
+
+    fn lines_left(&self, _progress: &Progress) -> Option<u32> {
+        None
+    }
+
+    fn endless(&self) -> bool {
+        false
+    }
+}
+
+// The lines cleared so far as counted by the line clear system, and the level they got the player to.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Progress {
+    pub lines: u32,
+    pub garbage_lines: u32, // cleared rows that had garbage in them
+    pub level: u32,
+}
+
+// Play until the stack reaches the top.
+pub struct Endless;
+
+impl Mode for Endless {}
+
+// Clear the lines as fast as possible.
+pub struct Sprint {
+    lines: u32,
+}
+
+impl Mode for Sprint {
+    fn goal_reached(&self, progress: &Progress) -> bool {
+        progress.lines >= self.lines
+    }
+
+    fn lines_left(&self, progress: &Progress) -> Option<u32> {
+        Some(self.lines.saturating_sub(progress.lines))
+    }
+}
+
+// Score as much as possible before the time runs out.
+pub struct Ultra {
+    time: Duration,
+}
+
+impl Mode for Ultra {
+    fn time_limit(&self) -> Option<Duration> {
+        Some(self.time)
+    }
+}
+
+// Dig through the garbage at the bottom of the board as fast as possible.
+pub struct Dig {
+    rows: u32,
+    messiness: u32,
+}
+
+impl Mode for Dig {
+    fn setup(&self, game: &mut Game, rng: &mut dyn RngCore) {
+        let width = game.board.width();
+        for hole in garbage_holes(self.rows, width, self.messiness, rng) {
+            game.board.push_garbage_row(hole);
+        }
+    }
+
+    fn goal_reached(&self, progress: &Progress) -> bool {
+        progress.garbage_lines >= self.rows
+    }
+
+    fn lines_left(&self, progress: &Progress) -> Option<u32> {
+        Some(self.rows.saturating_sub(progress.garbage_lines))
+    }
+}
+
+// When a marathon is won.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum MarathonGoal {
+    Lines(u32),
+    Level(u32),
+}
+
+// Keep clearing lines while it gets faster and faster, until the goal.
+pub struct Marathon {
+    goal: MarathonGoal,
+    endless: bool,
+}
+
+impl Mode for Marathon {
+    fn goal_reached(&self, progress: &Progress) -> bool {
+        match self.goal {
+            MarathonGoal::Lines(lines) => progress.lines >= lines,
+            MarathonGoal::Level(level) => progress.level >= level,
+        }
+    }
+
+    fn lines_left(&self, progress: &Progress) -> Option<u32> {
+        match self.goal {
+            MarathonGoal::Lines(lines) => Some(lines.saturating_sub(progress.lines)),
+            MarathonGoal::Level(_) => None,
+        }
+    }
+
+    fn endless(&self) -> bool {
+        self.endless
+    }
+}
+
+// Which game is being played.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum ModeKind {
+    #[default]
+    Endless,
+    Sprint { lines: u32 }, // clear this many lines as fast as possible, usually 20, 40 or 100
+    Ultra { seconds: u32 }, // score attack, usually 2 minutes
+    Dig { rows: u32, messiness: u32 }, // clear rows of garbage, messiness is how often the hole moves in percent
+    Marathon { goal: MarathonGoal, endless: bool }, // usually 150 lines or level 15, endless keeps going after that
+}
+
+impl ModeKind {
+    pub fn build(&self) -> Box<dyn Mode> {
+        match *self {
+            ModeKind::Endless => Box::new(Endless),
+            ModeKind::Sprint { lines } => Box::new(Sprint { lines }),
+            ModeKind::Ultra { seconds } => Box::new(Ultra {
+                time: Duration::from_secs(seconds.into()),
+            }),
+            ModeKind::Dig { rows, messiness } => Box::new(Dig { rows, messiness }),
+            ModeKind::Marathon { goal, endless } => Box::new(Marathon { goal, endless }),
+        }
+    }
+
+    // Modes that are played against the clock get a countdown, so the timer does not start by surprise.
+    pub fn timed(&self) -> bool {
+        match self {
+            ModeKind::Endless | ModeKind::Marathon { .. } => false,
+            ModeKind::Sprint { .. } | ModeKind::Ultra { .. } | ModeKind::Dig { .. } => true,
+        }
+    }
+}
+
+// The name shown in the menu, like "Sprint 40 lines".
+impl fmt::Display for ModeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ModeKind::Endless => write!(f, "Endless"),
+            ModeKind::Sprint { lines } => write!(f, "Sprint {} lines", lines),
+            ModeKind::Ultra { seconds } => write!(f, "Ultra {}:{:02}", seconds / 60, seconds % 60),
+            ModeKind::Dig { rows, .. } => write!(f, "Dig {} rows", rows),
+            ModeKind::Marathon { goal, endless } => {
+                match goal {
+                    MarathonGoal::Lines(lines) => write!(f, "Marathon {} lines", lines)?,
+                    MarathonGoal::Level(level) => write!(f, "Marathon level {}", level)?,
+                }
+                if endless {
+                    write!(f, " endless")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// How the game ended.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Ending {
+    TopOut(TopOut),
+    Finished, // the goal of the mode was reached
+    TimeUp,
+}
+
+/*
+Everything about the current game that is not on the board: which mode, how far along it is, how long it has been going
+and how it ended.
+The time only runs while the game does, so a countdown or a pause does not count.
+*/
+pub struct Session {
+    pub mode: ModeKind,
+    rules: Box<dyn Mode>,
+    progress: Progress,
+    frames: u64,                 // how many frames the game has been going
+    goal_time: Option<Duration>, // when the goal was reached, an endless mode keeps going after it
+    ending: Option<Ending>,
+}
+
+impl Session {
+    pub fn new(mode: ModeKind) -> Self {
+        Self {
+            mode,
+            rules: mode.build(),
+            progress: Progress::default(),
+            frames: 0,
+            goal_time: None,
+            ending: None,
+        }
+    }
+
+    // Must be called on the empty board, before the first piece spawns.
+    pub fn setup(&self, game: &mut Game, rng: &mut dyn RngCore) {
+        self.rules.setup(game, rng);
+    }
+
+    pub fn add_lines(&mut self, lines: u32, garbage_lines: u32) {
+        self.progress.lines += lines;
+        self.progress.garbage_lines += garbage_lines;
+    }
+
+    pub fn set_level(&mut self, level: u32) {
+        self.progress.level = level;
+    }
+
+    pub fn goal_time(&self) -> Option<Duration> {
+        self.goal_time
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_secs(self.frames) / FRAMES_PER_SECOND
+    }
+
+    pub fn ending(&self) -> Option<Ending> {
+        self.ending
+    }
+
+    /// Counts one frame and checks the mode, gives back the ending only once, when it happens.
+    pub fn update(&mut self, game: &Game) -> Option<Ending> {
+        if self.ending.is_some() {
+            return None;
+        }
+        self.frames += 1;
+
+        if self.goal_time.is_none() && self.rules.goal_reached(&self.progress) {
+            self.goal_time = Some(self.elapsed());
+        }
+
+        let time_limit = self.rules.time_limit();
+        self.ending = if let Some(top_out) = game.top_out() {
+            Some(Ending::TopOut(top_out))
+        } else if self.goal_time.is_some() && !self.rules.endless() {
+            Some(Ending::Finished)
+        } else if time_limit.is_some_and(|limit| self.elapsed() >= limit) {
+            Some(Ending::TimeUp)
+        } else {
+            None
+        };
+        self.ending
+    }
+
+    pub fn lines_left(&self) -> Option<u32> {
+        self.rules.lines_left(&self.progress)
+    }
+
+    pub fn time_left(&self) -> Option<Duration> {
+        self.rules
+            .time_limit()
+            .map(|limit| limit.checked_sub(self.elapsed()).unwrap_or_default())
+    }
+
+    pub fn pieces_per_second(&self, pieces: u32) -> f32 {
+        let seconds = self.elapsed().as_secs_f32();
+        if seconds > 0.0 {
+            pieces as f32 / seconds
+        } else {
+            0.0
+        }
+    }
+}
+
+// Minutes, seconds and milliseconds, like 1:02.345
+pub fn format_time(time: Duration) -> String {
+    let millis = time.as_millis();
+    format!(
+        "{}:{:02}.{:03}",
+        millis / 60_000,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::board::Cell;
+    use super::super::piece::{Piece, PieceType, Position};
+    use super::super::rng::SeededRng;
+    use super::*;
+
+    // A game where the next piece has no room left.
+    fn topped_out() -> Game {
+        let mut game = Game::new(10, 20);
+        for col in 0..10 {
+            game.board
+                .set(&Position { row: 20, col }, Some(Cell::Garbage));
+        }
+        assert!(!game.spawn(Piece::new(PieceType::T)));
+        game
+    }
+
+    #[test]
+    fn sprint_finishes_on_its_lines() {
+        let game = Game::new(10, 20);
+        let mut session = Session::new(ModeKind::Sprint { lines: 40 });
+        session.add_lines(39, 0);
+        assert_eq!(session.update(&game), None);
+        assert_eq!(session.lines_left(), Some(1));
+
+        session.add_lines(4, 0);
+        assert_eq!(session.update(&game), Some(Ending::Finished));
+        assert_eq!(session.lines_left(), Some(0));
+        // It only ends once, and the clock stops with it.
+        assert_eq!(session.update(&game), None);
+        assert_eq!(
+            session.elapsed(),
+            Duration::from_secs(2) / FRAMES_PER_SECOND
+        );
+    }
+
+    #[test]
+    fn top_out_comes_before_the_goal() {
+        let game = topped_out();
+        let mut session = Session::new(ModeKind::Sprint { lines: 40 });
+        session.add_lines(40, 0);
+        assert_eq!(
+            session.update(&game),
+            Some(Ending::TopOut(TopOut::BlockOut))
+        );
+    }
+
+    #[test]
+    fn time_runs_out() {
+        let game = Game::new(10, 20);
+        let mut session = Session::new(ModeKind::Ultra { seconds: 1 });
+        for _ in 1..FRAMES_PER_SECOND {
+            assert_eq!(session.update(&game), None);
+        }
+        assert_eq!(session.update(&game), Some(Ending::TimeUp));
+        assert_eq!(session.time_left(), Some(Duration::from_secs(0)));
+
+        // Past the limit it stays at zero.
+        session.frames += 100;
+        assert_eq!(session.time_left(), Some(Duration::from_secs(0)));
+        assert_eq!(Session::new(ModeKind::Endless).time_left(), None);
+    }
+    #[test]
+    fn dig_counts_garbage_lines_only() {
+        let mut game = Game::new(10, 20);
+        let mut rng = SeededRng::new(1);
+        let mut session = Session::new(ModeKind::Dig {
+            rows: 5,
+            messiness: 50,
+        });
+        session.setup(&mut game, &mut rng);
+        // Every garbage row has one hole, the rows above are empty.
+        let holes = |row| {
+            (0..10)
+                .filter(|col| game.board.is_free(&Position { row, col: *col }))
+                .count()
+        };
+        assert!((0..5).all(|row| holes(row) == 1));
+        assert_eq!(holes(5), 10);
+
+        // Clean lines above the garbage do not get it closer.
+        session.add_lines(10, 0);
+        assert_eq!(session.update(&game), None);
+        assert_eq!(session.lines_left(), Some(5));
+        session.add_lines(3, 3);
+        assert_eq!(session.update(&game), None);
+        session.add_lines(2, 2);
+        assert_eq!(session.update(&game), Some(Ending::Finished));
+    }
+    #[test]
+    fn endless_marathon_remembers_the_goal_and_goes_on() {
+        let game = Game::new(10, 20);
+        let mut session = Session::new(ModeKind::Marathon {
+            goal: MarathonGoal::Lines(150),
+            endless: true,
+        });
+        session.add_lines(149, 0);
+        assert_eq!(session.update(&game), None);
+        assert_eq!(session.goal_time(), None);
+
+        session.add_lines(1, 0);
+        assert_eq!(session.update(&game), None);
+        let goal_time = session.goal_time().expect("the goal was reached");
+        assert_eq!(goal_time, Duration::from_secs(2) / FRAMES_PER_SECOND);
+
+        // Later lines do not move it, and only a top out ends the game.
+        session.add_lines(30, 0);
+        for _ in 0..100 {
+            assert_eq!(session.update(&game), None);
+        }
+        assert_eq!(session.goal_time(), Some(goal_time));
+        assert_eq!(
+            session.update(&topped_out()),
+            Some(Ending::TopOut(TopOut::BlockOut))
+        );
+        assert_eq!(session.goal_time(), Some(goal_time));
+    }
+
+    #[test]
+    fn marathon_to_a_level() {
+        let game = Game::new(10, 20);
+        let mut session = Session::new(ModeKind::Marathon {
+            goal: MarathonGoal::Level(15),
+            endless: false,
+        });
+        // Lines alone do not do it, the level does.
+        session.add_lines(200, 0);
+        session.set_level(14);
+        assert_eq!(session.update(&game), None);
+        assert_eq!(session.lines_left(), None);
+
+        session.set_level(15);
+        assert_eq!(session.update(&game), Some(Ending::Finished));
+        assert!(session.goal_time().is_some());
+    }
+}
@@ -0,0 +1,126 @@
+// how tetriminos are formed, without any engine types so it can be tested on its own
+use rand::distributions::Standard;
+use rand::prelude::Distribution;
+use rand::Rng;
+
+//Normal Piece
+/*
+* Here is how we make a piece, it can be rotated.
+*/
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Piece {
+    pub piece_type: PieceType,
+    pub rotation: u8,
+}
+
+impl Piece {
+    pub fn new(piece_type: PieceType) -> Self {
+        Self {
+            piece_type,
+            rotation: 0,
+        }
+    }
+
+    pub fn rotate_cw(&mut self) {
+        self.rotation = (self.rotation + 1) % 4;
+    }
+
+    pub fn rotate_ccw(&mut self) {
+        // println!("{}",self.rotation);
+        if self.rotation == 0u8 {
+            self.rotation = (u8::MAX) % 4;
+        } else {
+            self.rotation = (self.rotation - 1) % 4;
+        }
+    }
+
+    pub fn get_filled_positions(&self, pos: &Position) -> Vec<Position> {
+        let mut positions = Vec::new();
+        let shape: PieceShape = self.piece_type.get_shape(self.rotation);
+        for row in 0..4 {
+            for col in 0..4 {
+                if (shape & (1 << (row * 4 + col))) != 0 {
+                    positions.push(Position {
+                        row: pos.row + (3 - row),
+                        col: pos.col + col,
+                    });
+                }
+            }
+        }
+        positions
+    }
+}
+
+// https://tetris.fandom.com/wiki/SRS
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PieceType {
+    O,
+    J,
+    L,
+    I,
+    S,
+    Z,
+    T,
+}
+
+/**
+Why don't we store it as u16 instead of an array?
+Normally, we would store it as an array2D to illustrate the piece in different shapes.
+See how I store it.
+*/
+type PieceShape = u16;
+
+impl PieceType {
+    pub const ALL: [PieceType; 7] = [
+        PieceType::O,
+        PieceType::J,
+        PieceType::L,
+        PieceType::I,
+        PieceType::S,
+        PieceType::Z,
+        PieceType::T,
+    ];
+
+    pub fn get_shape(&self, rotation: u8) -> PieceShape {
+        let shapes = match *self {
+            /*
+            Looks weird right? If you understand how bit operation work you will get it.
+            It is pretty simple, but I would say "magic".
+            Explaination : https://stackoverflow.com/questions/38594574/tetris-2d-array-logic
+
+            The shapes are ordered like the SRS rotation states (0, R, 2, L), so the kick tables line up.
+            In the spawn rotation every piece sits in the middle two rows of its box, so they all come in at the same height.
+            */
+            PieceType::O => [0x0660, 0x0660, 0x0660, 0x0660],
+            PieceType::J => [0x0E20, 0x44C0, 0x8E00, 0x6440],
+            PieceType::L => [0x0E80, 0xC440, 0x2E00, 0x4460],
+            PieceType::I => [0x00F0, 0x4444, 0x0F00, 0x2222],
+            PieceType::S => [0x06C0, 0x8C40, 0x6C00, 0x4620],
+            PieceType::Z => [0x0C60, 0x4C80, 0xC600, 0x2640],
+            PieceType::T => [0x0E40, 0x4C40, 0x4E00, 0x4640],
+        };
+        shapes[rotation as usize % 4]
+    }
+}
+
+// Simple rng for now
+impl Distribution<PieceType> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> PieceType {
+        match rng.gen_range(0, 7) {
+            0 => PieceType::O,
+            1 => PieceType::J,
+            2 => PieceType::L,
+            3 => PieceType::I,
+            4 => PieceType::S,
+            5 => PieceType::Z,
+            _ => PieceType::T,
+        }
+    }
+}
+
+// Track Positions, we see them as an object not matrix
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Position {
+    pub row: i16,
+    pub col: i16,
+}
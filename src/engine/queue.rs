@@ -0,0 +1,30 @@
+use super::piece::PieceType;
+use std::collections::VecDeque;
+
+/*
+The pieces that come after the active one, so the player (or a bot) can plan ahead.
+The spawner keeps it topped up and the HUD draws it.
+*/
+#[derive(Clone, Debug, Default)]
+pub struct PieceQueue {
+    pieces: VecDeque<PieceType>,
+}
+
+impl PieceQueue {
+    pub fn iter(&self) -> impl Iterator<Item = &PieceType> {
+        self.pieces.iter()
+    }
+
+    /// Adds pieces at the back until there are `length` of them.
+    pub fn fill(&mut self, length: usize, mut generate: impl FnMut() -> PieceType) {
+        while self.pieces.len() < length {
+            self.pieces.push_back(generate());
+        }
+    }
+
+    /// Takes the piece at the front and keeps `length` pieces waiting behind it.
+    pub fn next(&mut self, length: usize, mut generate: impl FnMut() -> PieceType) -> PieceType {
+        self.fill(length + 1, &mut generate);
+        self.pieces.pop_front().unwrap()
+    }
+}
@@ -0,0 +1,174 @@
+use super::piece::PieceType;
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+
+/*
+Which piece comes next is up to a randomizer, the spawner does not care how it is chosen.
+The rng is passed in so the same randomizer can be driven by any source of randomness.
+*/
+pub trait Randomizer: Send + Sync {
+    fn next_piece(&mut self, rng: &mut dyn RngCore) -> PieceType;
+}
+
+// Every piece is drawn on its own, this is how the game used to work.
+pub struct PureRandom;
+
+impl Randomizer for PureRandom {
+    fn next_piece(&mut self, rng: &mut dyn RngCore) -> PieceType {
+        rng.gen()
+    }
+}
+
+/*
+https://tetris.fandom.com/wiki/Random_Generator
+
+Random Generator generates a sequence of all seven one-sided tetrominoes
+(I, J, L, O, S, T, Z) permuted randomly, as if they were drawn from a bag.
+Then it deals all seven tetrominoes to the piece sequence before generating another bag.
+It can produce a maximum of 12 tetrominoes between one I and the next I, and a run of S and Z
+tetrominoes is limited to a maximum of 4.
+*/
+#[derive(Default)]
+pub struct SevenBag {
+    bag: Vec<PieceType>,
+}
+
+impl Randomizer for SevenBag {
+    fn next_piece(&mut self, rng: &mut dyn RngCore) -> PieceType {
+        if self.bag.is_empty() {
+            self.bag.extend_from_slice(&PieceType::ALL);
+            self.bag.shuffle(rng);
+        }
+        self.bag.pop().unwrap()
+    }
+}
+
+/*
+The Tetris The Grand Master way, https://tetris.fandom.com/wiki/TGM_randomizer
+We remember the last 4 pieces and roll again when we get one of them, up to a number of retries.
+The history starts with Z, Z, S, S and the first piece is never S, Z or O, so nobody starts with an overhang.
+*/
+pub struct History {
+    history: [PieceType; 4],
+    retries: u32,
+    first: bool,
+}
+
+impl History {
+    pub fn new(retries: u32) -> Self {
+        Self {
+            history: [PieceType::Z, PieceType::Z, PieceType::S, PieceType::S],
+            retries,
+            first: true,
+        }
+    }
+}
+
+impl Randomizer for History {
+    fn next_piece(&mut self, rng: &mut dyn RngCore) -> PieceType {
+        let piece = if self.first {
+            self.first = false;
+            *[PieceType::I, PieceType::J, PieceType::L, PieceType::T]
+                .choose(rng)
+                .unwrap()
+        } else {
+            let mut piece = rng.gen();
+            for _ in 0..self.retries {
+                if !self.history.contains(&piece) {
+                    break;
+                }
+                piece = rng.gen();
+            }
+            piece
+        };
+
+        self.history.rotate_left(1);
+        self.history[3] = piece;
+        piece
+    }
+}
+
+// The randomizers we can pick from the config file.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub enum RandomizerKind {
+    Random,
+    #[default]
+    SevenBag,
+    History { retries: u32 },
+}
+
+impl RandomizerKind {
+    pub fn build(&self) -> Box<dyn Randomizer> {
+        match *self {
+            RandomizerKind::Random => Box::new(PureRandom),
+            RandomizerKind::SevenBag => Box::new(SevenBag::default()),
+            RandomizerKind::History { retries } => Box::new(History::new(retries)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::rng::SeededRng;
+    use super::*;
+
+    fn draw(kind: RandomizerKind, seed: u64, count: usize) -> Vec<PieceType> {
+        let mut rng = SeededRng::new(seed);
+        let mut randomizer = kind.build();
+        (0..count)
+            .map(|_| randomizer.next_piece(&mut rng))
+            .collect()
+    }
+
+    #[test]
+    fn every_bag_has_every_piece_once() {
+        let pieces = draw(RandomizerKind::SevenBag, 1, 7 * 100);
+        for bag in pieces.chunks(7) {
+            for piece_type in PieceType::ALL.iter() {
+                assert_eq!(bag.iter().filter(|piece| *piece == piece_type).count(), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn history_never_starts_with_an_overhang() {
+        for seed in 0..200 {
+            let first = draw(RandomizerKind::History { retries: 6 }, seed, 1)[0];
+            assert!(
+                ![PieceType::S, PieceType::Z, PieceType::O].contains(&first),
+                "seed {} starts with {:?}",
+                seed,
+                first
+            );
+        }
+    }
+
+    #[test]
+    fn history_avoids_the_last_pieces() {
+        // With this many retries a repeat would need 100 unlucky rolls in a row.
+        let pieces = draw(RandomizerKind::History { retries: 100 }, 2, 1000);
+        for (index, piece) in pieces.iter().enumerate() {
+            let recent = &pieces[index.saturating_sub(4)..index];
+            assert!(!recent.contains(piece), "{:?} after {:?}", piece, recent);
+        }
+
+        // Without retries it is plain random, the same piece twice in a row happens all the time.
+        let pieces = draw(RandomizerKind::History { retries: 0 }, 2, 1000);
+        assert!(pieces.windows(2).any(|pair| pair[0] == pair[1]));
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_pieces() {
+        for kind in [
+            RandomizerKind::Random,
+            RandomizerKind::SevenBag,
+            RandomizerKind::History { retries: 6 },
+        ]
+        .iter()
+        {
+            assert_eq!(draw(*kind, 42, 200), draw(*kind, 42, 200));
+            assert_ne!(draw(*kind, 42, 200), draw(*kind, 43, 200));
+        }
+    }
+}
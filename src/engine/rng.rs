@@ -0,0 +1,43 @@
+use rand::{Error, RngCore, SeedableRng};
+use rand_pcg::Pcg32;
+
+/*
+Where the randomness of a game comes from, the pieces and the garbage of a dig.
+It is a PCG stream, it gives the same numbers for the same seed on every machine,
+so two players with the same seed get the same pieces and a replay only needs the seed.
+*/
+pub struct SeededRng {
+    seed: u64,
+    rng: Pcg32,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: Pcg32::seed_from_u64(seed),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl RngCore for SeededRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
@@ -0,0 +1,135 @@
+use super::t_spin::TSpin;
+
+/*
+Guideline scoring, https://tetris.fandom.com/wiki/Scoring
+Line clears are worth more on higher levels (the level before the clear counts), clearing in a row builds a combo,
+and a difficult clear (a tetris or a T-spin) right after another one is worth half as much again.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct Score {
+    pub points: u32,
+    pub combo: i32, // -1 when the last piece did not clear anything
+    pub back_to_back: bool,
+}
+
+impl Default for Score {
+    fn default() -> Self {
+        Self {
+            points: 0,
+            combo: -1,
+            back_to_back: false,
+        }
+    }
+}
+
+impl Score {
+    /// Soft drop is worth 1 point per row and hard drop 2, whatever the level.
+    pub fn add_drop(&mut self, soft_drop_rows: u32, hard_drop_rows: u32) {
+        self.points += soft_drop_rows + hard_drop_rows * 2;
+    }
+
+    /// Scores a piece that just landed and returns what it was worth.
+    pub fn add_landing(
+        &mut self,
+        lines: usize,
+        t_spin: TSpin,
+        perfect_clear: bool,
+        level: u32,
+    ) -> u32 {
+        // A T-spin without lines is still worth something, but it does not touch back-to-back.
+        if lines == 0 {
+            self.combo = -1;
+            let points = match t_spin {
+                TSpin::None => 0,
+                TSpin::Mini => 100,
+                TSpin::Full => 400,
+            } * level;
+            self.points += points;
+            return points;
+        }
+
+        let difficult = lines >= 4 || t_spin != TSpin::None;
+        let back_to_back = difficult && self.back_to_back;
+        self.back_to_back = difficult;
+        self.combo += 1;
+
+        let mut points = match (t_spin, lines) {
+            (TSpin::None, 1) => 100,
+            (TSpin::None, 2) => 300,
+            (TSpin::None, 3) => 500,
+            (TSpin::None, _) => 800,
+            (TSpin::Mini, 1) => 200,
+            (TSpin::Mini, _) => 400,
+            (TSpin::Full, 1) => 800,
+            (TSpin::Full, 2) => 1200,
+            (TSpin::Full, _) => 1600,
+        };
+        if back_to_back {
+            points = points * 3 / 2;
+        }
+
+        points += 50 * self.combo as u32;
+
+        if perfect_clear {
+            points += match lines {
+                1 => 800,
+                2 => 1200,
+                3 => 1800,
+                _ if back_to_back => 3200,
+                _ => 2000,
+            };
+        }
+
+        let points = points * level;
+        self.points += points;
+        points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combo_adds_50_for_every_clear_in_a_row() {
+        let mut score = Score::default();
+        assert_eq!(score.add_landing(1, TSpin::None, false, 1), 100);
+        assert_eq!(score.add_landing(1, TSpin::None, false, 1), 150);
+        assert_eq!(score.add_landing(2, TSpin::None, false, 1), 400);
+        assert_eq!(score.combo, 2);
+
+        // A piece that clears nothing breaks the combo.
+        assert_eq!(score.add_landing(0, TSpin::None, false, 1), 0);
+        assert_eq!(score.combo, -1);
+        assert_eq!(score.add_landing(1, TSpin::None, false, 2), 200);
+        assert_eq!(score.points, 100 + 150 + 400 + 200);
+    }
+
+    #[test]
+    fn back_to_back_is_worth_half_again() {
+        let mut score = Score::default();
+        assert_eq!(score.add_landing(4, TSpin::None, false, 1), 800);
+        assert!(score.back_to_back);
+        score.add_landing(0, TSpin::None, false, 1);
+        assert_eq!(score.add_landing(2, TSpin::Full, false, 1), 1800);
+
+        // An easy clear ends it, a T-spin without lines does not.
+        score.add_landing(0, TSpin::Full, false, 1);
+        assert!(score.back_to_back);
+        score.add_landing(0, TSpin::None, false, 1);
+        assert_eq!(score.add_landing(1, TSpin::None, false, 1), 100);
+        assert!(!score.back_to_back);
+        score.add_landing(0, TSpin::None, false, 1);
+        assert_eq!(score.add_landing(4, TSpin::None, false, 1), 800);
+    }
+
+    #[test]
+    fn perfect_clear_bonus() {
+        let mut score = Score::default();
+        assert_eq!(score.add_landing(1, TSpin::None, true, 1), 900);
+        score.add_landing(0, TSpin::None, false, 1);
+        assert_eq!(score.add_landing(4, TSpin::None, true, 2), (800 + 2000) * 2);
+        score.add_landing(0, TSpin::None, false, 1);
+        assert_eq!(score.add_landing(4, TSpin::None, true, 1), 1200 + 3200);
+    }
+}
@@ -0,0 +1,127 @@
+use super::board::Board;
+use super::game::ActivePiece;
+use super::piece::{PieceType, Position};
+
+// What kind of T-spin the last piece was, if any.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TSpin {
+    None,
+    Mini,
+    Full,
+}
+
+// The last kick of the SRS table, the one that lifts a T piece into a TST or fin slot.
+const LAST_KICK: usize = 4;
+
+/*
+The 3-corner rule, https://tetris.wiki/T-Spin
+A T piece that got into place with a rotation is a T-spin when 3 of the 4 cells around its center are blocked,
+the walls and the floor count too. If one of the two corners it is pointing at is open it is only a mini,
+unless the rotation needed the last kick, that one is always a full T-spin.
+*/
+pub fn detect(board: &Board, active: &ActivePiece, kick: usize) -> TSpin {
+    if active.piece.piece_type != PieceType::T {
+        return TSpin::None;
+    }
+
+    // The center of the T is in the same spot of the 4x4 box in every rotation.
+    let center = Position {
+        row: active.position.row + 1,
+        col: active.position.col + 2,
+    };
+    let blocked = |(rows, cols): (i16, i16)| {
+        !board.is_free(&Position {
+            row: center.row + rows,
+            col: center.col + cols,
+        })
+    };
+
+    // The two corners on the side the T is pointing at come first, the two behind it after them.
+    let corners = match active.piece.rotation % 4 {
+        0 => [(1, -1), (1, 1), (-1, -1), (-1, 1)],
+        1 => [(1, 1), (-1, 1), (1, -1), (-1, -1)],
+        2 => [(-1, -1), (-1, 1), (1, -1), (1, 1)],
+        _ => [(1, -1), (-1, -1), (1, 1), (-1, 1)],
+    };
+    let front = corners[..2]
+        .iter()
+        .filter(|corner| blocked(**corner))
+        .count();
+    let back = corners[2..]
+        .iter()
+        .filter(|corner| blocked(**corner))
+        .count();
+
+    if front + back < 3 {
+        TSpin::None
+    } else if front == 2 || kick == LAST_KICK {
+        TSpin::Full
+    } else {
+        TSpin::Mini
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::board::Cell;
+    use super::super::piece::Piece;
+    use super::*;
+
+    fn board_with(cells: &[(i16, i16)]) -> Board {
+        let mut board = Board::new(10, 20);
+        for (row, col) in cells {
+            board.set(
+                &Position {
+                    row: *row,
+                    col: *col,
+                },
+                Some(Cell::Garbage),
+            );
+        }
+        board
+    }
+
+    fn t_piece(rotation: u8, row: i16, col: i16) -> ActivePiece {
+        let mut piece = Piece::new(PieceType::T);
+        piece.rotation = rotation;
+        ActivePiece {
+            piece,
+            position: Position { row, col },
+        }
+    }
+
+    #[test]
+    fn t_spin_double() {
+        // Pointing down into the slot with its center at row 1, col 5, both corners under it are filled.
+        let board = board_with(&[(0, 4), (0, 6), (2, 4)]);
+        let active = t_piece(2, 0, 3);
+        assert_eq!(detect(&board, &active, 0), TSpin::Full);
+        assert_eq!(detect(&board, &active, 2), TSpin::Full);
+    }
+
+    #[test]
+    fn mini_with_an_open_front_corner() {
+        // Pointing up on the floor, the floor blocks both back corners but only one front corner is filled.
+        let board = board_with(&[(1, 4)]);
+        let active = t_piece(0, -1, 3);
+        assert_eq!(detect(&board, &active, 0), TSpin::Mini);
+    }
+
+    #[test]
+    fn last_kick_makes_it_full() {
+        let board = board_with(&[(1, 4)]);
+        let active = t_piece(0, -1, 3);
+        assert_eq!(detect(&board, &active, LAST_KICK), TSpin::Full);
+    }
+
+    #[test]
+    fn not_enough_corners() {
+        let board = board_with(&[(0, 4), (0, 6)]);
+        assert_eq!(detect(&board, &t_piece(2, 0, 3), 0), TSpin::None);
+
+        let mut active = t_piece(2, 0, 3);
+        active.piece.piece_type = PieceType::S;
+        let board = board_with(&[(0, 4), (0, 6), (2, 4)]);
+        assert_eq!(detect(&board, &active, 0), TSpin::None);
+    }
+}
@@ -1,3 +1,3 @@
 mod rustrimino;
 
-pub use self::rustrimino::{DroppedPiece, Piece, PieceType, Position};
+pub use self::rustrimino::{hide_above_field, DroppedPiece, GarbageBlock};
@@ -1,9 +1,34 @@
+use crate::engine::{Ending, TSpin};
+
 /**
 Normally, we would track an event by having a variable that is mutable and send signal to trigger event.
 Likewise, this event system in amethyst will do that for you.
 */
 
+// A piece has been locked into the board, and these rows were cleared because of it.
+// The drop rows, the perfect clear and the T-spin are only there for the score.
+#[derive(Debug)]
+pub struct PieceLandEvent {
+    pub cleared_rows: Vec<i16>,
+    pub soft_drop_rows: u32,
+    pub hard_drop_rows: u32,
+    pub perfect_clear: bool,
+    pub t_spin: TSpin,
+}
+
+// Rows were cleared, the mode counts them towards its goal. Garbage rows are counted on their own as well.
 #[derive(Debug)]
-pub struct ResetFallTimerEvent {}
+pub struct LineClearEvent {
+    pub lines: u32,
+    pub garbage_lines: u32,
+}
+
+// The active piece went into the hold slot, if nothing came back out we need a new one.
+#[derive(Debug)]
+pub struct PieceHoldEvent {}
+
+// The stack reached the top or the goal of the mode was reached, the state will stop the game when it sees this.
 #[derive(Debug)]
-pub struct PieceLandEvent {}
+pub struct GameOverEvent {
+    pub ending: Ending,
+}
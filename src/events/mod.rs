@@ -1,3 +1,5 @@
 mod events;
 
-pub use self::{events::PieceLandEvent, events::ResetFallTimerEvent};
+pub use self::{
+    events::GameOverEvent, events::LineClearEvent, events::PieceHoldEvent, events::PieceLandEvent,
+};
@@ -13,18 +13,21 @@ use amethyst::{
 };
 use amethyst::renderer::RenderDebugLines;
 
-mod state;
+mod states;
 mod audio;
-mod constants;
+mod config;
+mod engine;
 mod entities;
 mod events;
+mod records;
+mod replay;
 mod systems;
 
-use crate::{
-    systems::{RenderSystem, PieceSpawnSystem, PieceInputSystem, LineClearSystem, DroppingSystem}
-};
 use crate::audio::Music;
+use crate::config::{GameConfig, HandlingConfig};
 use amethyst::audio::AudioBundle;
+use amethyst::config::Config;
+use std::env;
 
 fn main() -> amethyst::Result<()> {
     amethyst::start_logger(Default::default());
@@ -34,9 +37,19 @@ fn main() -> amethyst::Result<()> {
     let resources = app_root.join("assets");
     let display_config = app_root.join("config/display_config.ron");
     let key_bindings_path = app_root.join("config/input.ron");
+    // A bad value in the config stops the game right here, with the name of the field.
+    let mut game_config = GameConfig::load(app_root.join(GameConfig::FILE))?;
+    game_config.validate()?;
+    if let Some(seed) = seed_from_args()? {
+        game_config.seed = Some(seed);
+    }
+    let handling_config = HandlingConfig::load(app_root.join(HandlingConfig::FILE))?;
+    handling_config.validate()?;
 
     /* Here is how we make everything run together.
-    with_bundle will take all the systems and run those systems in parallel
+    with_bundle will take all the systems and run those systems in parallel.
+    The gameplay systems are not here, the GameState runs them so they stop when the game is over.
+    The game starts at the MainMenuState, which pushes a GameState for the mode that was picked.
     */
     let game_data = GameDataBuilder::default()
         .with_bundle(TransformBundle::new())?
@@ -55,23 +68,35 @@ fn main() -> amethyst::Result<()> {
                 .with_plugin(RenderDebugLines::default()),
 
         )?.with_bundle(UiBundle::<StringBindings>::new())?
-        .with(
-            PieceInputSystem::new(),
-            "piece_input_system",
-            &["input_system"],
-        )
-        .with(DroppingSystem::new(), "piece_drop_system", &[])
-        .with(PieceSpawnSystem::new(), "piece_spawn_system", &[])
-        .with(LineClearSystem::new(), "line_clear_system", &[])
-        .with(RenderSystem, "render_system", &[])
         .with_system_desc(
             DjSystemDesc::new(|music: &mut Music| music.music.next()),
             "dj_system",
             &[],
         );
 
-    let mut game = Application::new(resources, state::GameState, game_data)?;
+    let mut game = Application::build(resources, states::MainMenuState::default())?
+        .with_resource(game_config)
+        .with_resource(handling_config)
+        .build(game_data)?;
     game.run();
 
     Ok(())
 }
+
+// The seed from the command line, like `rustris --seed 1234`, it goes over the one in the config.
+fn seed_from_args() -> amethyst::Result<Option<u64>> {
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = if arg == "--seed" {
+            args.next().unwrap_or_default()
+        } else if let Some(value) = arg.strip_prefix("--seed=") {
+            value.to_string()
+        } else {
+            continue;
+        };
+        return value.parse().map(Some).map_err(|_| {
+            amethyst::Error::from_string(format!("--seed needs a number, not {:?}", value))
+        });
+    }
+    Ok(None)
+}
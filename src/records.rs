@@ -0,0 +1,397 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use amethyst::{config::Config, utils::application_root_dir};
+
+use crate::config::GameConfig;
+use crate::engine::{format_time, Ending, ModeKind};
+
+// How many results are kept for every mode.
+pub const TOP: usize = 10;
+
+// One finished game on the leaderboard.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RecordEntry {
+    pub name: String,
+    pub score: u32,
+    pub lines: u32,
+    pub time: u64, // milliseconds
+    pub date: u64, // seconds since 1970
+}
+
+impl RecordEntry {
+    pub fn new(name: &str, score: u32, lines: u32, time: Duration) -> Self {
+        Self {
+            name: name.to_string(),
+            score,
+            lines,
+            time: time.as_millis() as u64,
+            date: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|now| now.as_secs())
+                .unwrap_or(0),
+        }
+    }
+
+    pub fn time(&self) -> Duration {
+        Duration::from_millis(self.time)
+    }
+
+    // What the mode is played for, the time of a race or the score of everything else.
+    pub fn result(&self, mode: ModeKind) -> String {
+        if by_time(mode) {
+            format_time(self.time())
+        } else {
+            self.score.to_string()
+        }
+    }
+}
+
+// Sprint and dig are races, the other modes are played for points.
+fn by_time(mode: ModeKind) -> bool {
+    match mode {
+        ModeKind::Sprint { .. } | ModeKind::Dig { .. } => true,
+        ModeKind::Endless | ModeKind::Ultra { .. } | ModeKind::Marathon { .. } => false,
+    }
+}
+
+// A race only counts when it was finished, a score always counts.
+pub fn counts(mode: ModeKind, ending: Option<Ending>) -> bool {
+    !by_time(mode) || ending == Some(Ending::Finished)
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ModeRecords {
+    pub mode: ModeKind,
+    pub entries: Vec<RecordEntry>, // the best one first
+}
+
+/*
+The best games of every mode, kept in records.ron in the data folder of the user,
+unless gameplay.ron says where. A new file is written next to it first and then moved over the old one,
+so a crash in the middle of saving leaves the old records alone.
+A file that is broken is kept as records.corrupt.ron and we start over without it,
+if there is one already it becomes records.corrupt-2.ron and so on.
+A file that is there but cannot be read right now is never saved over, the games of this run are just not kept.
+*/
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Records {
+    #[serde(skip)]
+    path: PathBuf,
+    #[serde(skip)]
+    read_only: bool,
+    pub modes: Vec<ModeRecords>,
+}
+
+impl Records {
+    pub fn path(config: &GameConfig) -> PathBuf {
+        match &config.records_file {
+            Some(file) => PathBuf::from(file),
+            None => data_dir()
+                .or_else(|| application_root_dir().ok())
+                .unwrap_or_default()
+                .join("rustris")
+                .join("records.ron"),
+        }
+    }
+
+    pub fn read(config: &GameConfig) -> Self {
+        Self::read_path(Self::path(config))
+    }
+
+    fn read_path(path: PathBuf) -> Self {
+        let records = match fs::read(&path) {
+            Ok(bytes) => Self::load_bytes(&bytes).unwrap_or_else(|error| {
+                let corrupt = corrupt_path(&path);
+                log::warn!(
+                    "The records in {:?} are broken ({}), they are moved to {:?}",
+                    path,
+                    error,
+                    corrupt
+                );
+                // If it cannot be moved away, saving would be the end of it.
+                let read_only = match fs::rename(&path, &corrupt) {
+                    Ok(()) => false,
+                    Err(error) => {
+                        log::warn!("Could not move the broken records: {}", error);
+                        true
+                    }
+                };
+                Self {
+                    read_only,
+                    ..Self::default()
+                }
+            }),
+            Err(ref error) if error.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(error) => {
+                log::warn!(
+                    "Could not read the records in {:?} ({}), they are not saved this time",
+                    path,
+                    error
+                );
+                Self {
+                    read_only: true,
+                    ..Self::default()
+                }
+            }
+        };
+        Self { path, ..records }
+    }
+
+    pub fn save(&self) {
+        if self.read_only {
+            log::warn!(
+                "The records in {:?} could not be read, they are left alone",
+                self.path
+            );
+            return;
+        }
+        if let Err(error) = self.write_atomic(&self.path) {
+            log::warn!("Could not save the records to {:?}: {}", self.path, error);
+        }
+    }
+
+    fn write_atomic(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let text = ron::ser::to_string_pretty(self, Default::default())?;
+        let temporary = path.with_extension("tmp.ron");
+        // Make sure it is on the disk before it replaces the old file,
+        // and closed too, Windows does not move a file that is still open.
+        {
+            let mut file = File::create(&temporary)?;
+            file.write_all(text.as_bytes())?;
+            file.sync_all()?;
+        }
+        fs::rename(&temporary, path)?;
+        Ok(())
+    }
+
+    pub fn top(&self, mode: ModeKind) -> &[RecordEntry] {
+        self.modes
+            .iter()
+            .find(|records| records.mode == mode)
+            .map(|records| records.entries.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Puts the game on the leaderboard of its mode, gives back the place it got if it made it.
+    pub fn add(&mut self, mode: ModeKind, entry: RecordEntry) -> Option<usize> {
+        let index = match self.modes.iter().position(|records| records.mode == mode) {
+            Some(index) => index,
+            None => {
+                self.modes.push(ModeRecords {
+                    mode,
+                    entries: Vec::new(),
+                });
+                self.modes.len() - 1
+            }
+        };
+        let entries = &mut self.modes[index].entries;
+
+        // Behind everything it does not beat, so an equal result keeps the older one in front.
+        let place = entries
+            .iter()
+            .take_while(|other| {
+                if by_time(mode) {
+                    other.time <= entry.time
+                } else {
+                    other.score >= entry.score
+                }
+            })
+            .count();
+        if place >= TOP {
+            return None;
+        }
+        entries.insert(place, entry);
+        entries.truncate(TOP);
+        Some(place)
+    }
+}
+
+// records.corrupt.ron, or the first of records.corrupt-2.ron, records.corrupt-3.ron... that is not taken yet.
+fn corrupt_path(path: &Path) -> PathBuf {
+    let mut corrupt = path.with_extension("corrupt.ron");
+    let mut count = 1;
+    while corrupt.exists() {
+        count += 1;
+        corrupt = path.with_extension(format!("corrupt-{}.ron", count));
+    }
+    corrupt
+}
+
+// Where programs keep their files for this user.
+fn data_dir() -> Option<PathBuf> {
+    let home = env::var_os("HOME").map(PathBuf::from);
+    if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home.map(|home| home.join("Library").join("Application Support"))
+    } else {
+        env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| home.map(|home| home.join(".local").join("share")))
+    }
+}
+
+// The day of a record like 2020-12-31, in UTC.
+pub fn format_date(seconds: u64) -> String {
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(score: u32, time: u64, date: u64) -> RecordEntry {
+        RecordEntry {
+            name: "Player".to_string(),
+            score,
+            lines: 40,
+            time,
+            date,
+        }
+    }
+
+    // An empty folder of its own for every test, they run at the same time.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("rustris-records-{}-{}", name, std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn score_modes_keep_the_highest_first() {
+        let mut records = Records::default();
+        let mode = ModeKind::Endless;
+        assert_eq!(records.add(mode, entry(100, 0, 1)), Some(0));
+        assert_eq!(records.add(mode, entry(300, 0, 2)), Some(0));
+        assert_eq!(records.add(mode, entry(200, 0, 3)), Some(1));
+        // The same score goes behind the one that was there first.
+        assert_eq!(records.add(mode, entry(200, 0, 4)), Some(2));
+
+        let dates = records
+            .top(mode)
+            .iter()
+            .map(|entry| entry.date)
+            .collect::<Vec<_>>();
+        assert_eq!(dates, vec![2, 3, 4, 1]);
+    }
+
+    #[test]
+    fn races_keep_the_fastest_first() {
+        let mut records = Records::default();
+        let mode = ModeKind::Sprint { lines: 40 };
+        assert_eq!(records.add(mode, entry(0, 90_000, 1)), Some(0));
+        assert_eq!(records.add(mode, entry(9_999, 60_000, 2)), Some(0));
+        assert_eq!(records.add(mode, entry(0, 90_000, 3)), Some(2));
+        assert_eq!(records.top(mode)[0].date, 2);
+        assert!(records.top(ModeKind::Endless).is_empty());
+    }
+
+    #[test]
+    fn only_the_top_is_kept() {
+        let mut records = Records::default();
+        let mode = ModeKind::Endless;
+        for score in 1..=TOP as u32 {
+            records.add(mode, entry(score * 100, 0, 0));
+        }
+        assert_eq!(records.add(mode, entry(100, 0, 0)), None);
+        assert_eq!(records.add(mode, entry(50, 0, 0)), None);
+        assert_eq!(records.add(mode, entry(150, 0, 0)), Some(TOP - 1));
+
+        let top = records.top(mode);
+        assert_eq!(top.len(), TOP);
+        assert_eq!(top[0].score, TOP as u32 * 100);
+        assert_eq!(top[TOP - 1].score, 150);
+    }
+
+    #[test]
+    fn unfinished_races_do_not_count() {
+        let sprint = ModeKind::Sprint { lines: 40 };
+        assert!(counts(sprint, Some(Ending::Finished)));
+        assert!(!counts(sprint, None));
+        assert!(counts(ModeKind::Endless, None));
+        assert!(counts(ModeKind::Ultra { seconds: 120 }, None));
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+    }
+
+    #[test]
+    fn saved_records_are_read_back() {
+        let path = temp_dir("saved").join("records.ron");
+        let mut records = Records::read_path(path.clone());
+        assert!(records.modes.is_empty());
+        records.add(ModeKind::Endless, entry(100, 0, 1));
+        records.save();
+
+        let records = Records::read_path(path);
+        assert_eq!(records.top(ModeKind::Endless)[0].score, 100);
+    }
+
+    #[test]
+    fn broken_records_are_kept_aside() {
+        let dir = temp_dir("broken");
+        let path = dir.join("records.ron");
+        fs::write(&path, "not records").unwrap();
+        let records = Records::read_path(path.clone());
+        assert!(records.modes.is_empty());
+        assert!(!path.exists());
+
+        // A second broken file does not take the place of the first one.
+        fs::write(&path, "still not records").unwrap();
+        let mut records = Records::read_path(path.clone());
+        assert_eq!(
+            fs::read_to_string(dir.join("records.corrupt.ron")).unwrap(),
+            "not records"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("records.corrupt-2.ron")).unwrap(),
+            "still not records"
+        );
+
+        // Starting over is fine, they are safe.
+        records.add(ModeKind::Endless, entry(100, 0, 1));
+        records.save();
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn unreadable_records_are_not_saved_over() {
+        // A folder where the file should be cannot be read, like a file that is locked or not ours.
+        let path = temp_dir("unreadable").join("records.ron");
+        fs::create_dir(&path).unwrap();
+
+        let mut records = Records::read_path(path.clone());
+        assert!(records.modes.is_empty());
+        records.add(ModeKind::Endless, entry(100, 0, 1));
+        records.save();
+        assert!(path.is_dir());
+    }
+}
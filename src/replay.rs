@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use amethyst::config::Config;
+
+use crate::config::{GameConfig, HandlingConfig};
+use crate::engine::{ModeKind, PlayerInput};
+use crate::records::Records;
+
+// How many of the last games are kept, the older replays are deleted when a new one is saved.
+const KEEP: usize = 20;
+
+/*
+A whole game in a few lines: the seed of the pieces, the rules it was played with,
+and the input of every frame where it changed. Played back through the same systems
+it ends up exactly like the game it was recorded from.
+The replays go in a replays folder next to the records, one file for every game.
+*/
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Replay {
+    pub mode: ModeKind,
+    pub seed: u64,
+    pub config: GameConfig,
+    pub handling: HandlingConfig,
+    pub inputs: Vec<(u64, PlayerInput)>, // the frame the input changed on, and what it changed to
+}
+
+impl Replay {
+    pub fn new(mode: ModeKind, seed: u64, config: GameConfig, handling: HandlingConfig) -> Self {
+        Self {
+            mode,
+            seed,
+            config,
+            handling,
+            inputs: Vec::new(),
+        }
+    }
+
+    // Only a change is written down, a held key is one entry however long it is held.
+    pub fn record(&mut self, frame: u64, input: PlayerInput) {
+        let last = self
+            .inputs
+            .last()
+            .map(|(_, input)| *input)
+            .unwrap_or_default();
+        if input != last {
+            self.inputs.push((frame, input));
+        }
+    }
+
+    fn dir(config: &GameConfig) -> PathBuf {
+        Records::path(config).with_file_name("replays")
+    }
+
+    pub fn save(&self, config: &GameConfig) {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|now| now.as_millis())
+            .unwrap_or(0);
+        let path = Self::dir(config).join(format!("replay-{:015}.ron", millis));
+        if let Err(error) = self.write_file(&path) {
+            log::warn!("Could not save the replay to {:?}: {}", path, error);
+            return;
+        }
+        log::info!("The replay is saved to {:?}", path);
+
+        let mut replays = Self::files(config);
+        let old = replays.len().saturating_sub(KEEP);
+        for file in replays.drain(..old) {
+            fs::remove_file(file).ok();
+        }
+    }
+
+    fn write_file(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        self.write(path)?;
+        Ok(())
+    }
+
+    // The replay files from the oldest to the newest, the time is in the name.
+    fn files(config: &GameConfig) -> Vec<PathBuf> {
+        let mut files = fs::read_dir(Self::dir(config))
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| {
+                        let name = path.file_name().and_then(|name| name.to_str());
+                        let name = name.unwrap_or_default();
+                        name.starts_with("replay-") && name.ends_with(".ron")
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        files.sort();
+        files
+    }
+
+    pub fn latest(config: &GameConfig) -> Option<Self> {
+        let path = Self::files(config).pop()?;
+        match Self::load(&path) {
+            Ok(replay) => Some(replay),
+            Err(error) => {
+                log::warn!("Could not read the replay {:?}: {}", path, error);
+                None
+            }
+        }
+    }
+}
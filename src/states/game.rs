@@ -0,0 +1,592 @@
+use amethyst::{
+    assets::{AssetStorage, Handle, Loader},
+    core::{math::Point3, transform::Transform, ArcThreadPool, Time},
+    core::math::Vector3,
+    ecs::{Dispatcher, DispatcherBuilder, Entity, ReaderId},
+    input::InputEvent,
+    prelude::*,
+    renderer::{
+        palette::Srgba, resources::Tint, Camera, ImageFormat, SpriteRender, SpriteSheet,
+        SpriteSheetFormat, Texture,
+    },
+    ui::{
+        Anchor, FontHandle, LineMode, TtfFormat, UiImage, UiText,
+        UiTransform,
+    },
+};
+
+use crate::audio::initialise_audio;
+use crate::config::{GameConfig, HandlingConfig};
+use crate::engine::{
+    frame_time, frames, Cell, Game, Level, ModeKind, Piece, PieceQueue, PlayerInput, Position,
+    Randomizer, Score, SeededRng, Session,
+};
+use crate::entities::GarbageBlock;
+use crate::events::{GameOverEvent, LineClearEvent, PieceLandEvent};
+use crate::replay::Replay;
+use crate::states::{GameOverState, PausedState};
+use crate::systems::{
+    DroppingSystem, HudSystem, HudText, InputRecordSystem, LineClearSystem, ModeSystem,
+    PieceInputSystem, PieceSpawnSystem, RenderSystem, ReplayInputSystem, ScoreSystem,
+};
+use amethyst::core::ecs::shrev::EventChannel;
+use amethyst::renderer::debug_drawing::DebugLinesComponent;
+use amethyst::window::ScreenDimensions;
+use std::time::Duration;
+
+// The side panel sticks to the right edge of the window, the board gets the rest of it.
+const PANEL_WIDTH: f32 = 170.0;
+// Seconds before a timed mode starts.
+const COUNTDOWN: f32 = 3.0;
+// After a long hitch the game does not try to catch up with more frames than this at once.
+const MAX_CATCH_UP: u32 = 5;
+
+/*
+The gameplay systems belong to this state instead of the whole application,
+so once we leave the state nothing moves anymore. The engine stuff (rendering, input, audio) stays global.
+Drawing the board and the panel is split from the gameplay, so the countdown can show them while nothing moves.
+Every game is recorded and saved as a replay when it is over, a replay runs the same systems
+with the input it recorded instead of the keyboard.
+*/
+pub struct GameState {
+    mode: ModeKind,
+    replay: Option<Replay>, // the one that is played back, None for a game that is played
+    lag: Duration,          // how far the gameplay is behind the clock
+    gameplay: Option<Dispatcher<'static, 'static>>,
+    view: Option<Dispatcher<'static, 'static>>,
+    game_over_reader: Option<ReaderId<GameOverEvent>>,
+    countdown: f32,
+    countdown_text: Option<Entity>,
+}
+
+impl GameState {
+    pub fn new(mode: ModeKind) -> Self {
+        Self {
+            mode,
+            replay: None,
+            lag: Duration::default(),
+            gameplay: None,
+            view: None,
+            game_over_reader: None,
+            countdown: 0.0,
+            countdown_text: None,
+        }
+    }
+
+    // The game of a replay, played back with its own input.
+    pub fn watch(replay: Replay) -> Self {
+        let mut state = Self::new(replay.mode);
+        state.replay = Some(replay);
+        state
+    }
+
+    fn build_gameplay(
+        &self,
+        world: &mut World,
+        config: &GameConfig,
+        handling: HandlingConfig,
+        randomizer: Box<dyn Randomizer>,
+    ) -> Dispatcher<'static, 'static> {
+        let mut builder =
+            DispatcherBuilder::new().with_pool((*world.read_resource::<ArcThreadPool>()).clone());
+        match &self.replay {
+            Some(replay) => builder.add(ReplayInputSystem::new(replay), "player_input_system", &[]),
+            None => builder.add(InputRecordSystem::default(), "player_input_system", &[]),
+        }
+        let mut dispatcher = builder
+            .with(
+                PieceInputSystem::new(handling),
+                "piece_input_system",
+                &["player_input_system"],
+            )
+            .with(DroppingSystem::new(), "piece_drop_system", &[])
+            .with(
+                PieceSpawnSystem::new(randomizer, config.preview_count),
+                "piece_spawn_system",
+                &[],
+            )
+            .with(LineClearSystem::new(), "line_clear_system", &["piece_drop_system"])
+            .with(ScoreSystem::new(), "score_system", &["piece_drop_system"])
+            .with(
+                ModeSystem::new(),
+                "mode_system",
+                &["line_clear_system", "score_system"],
+            )
+            .build();
+        dispatcher.setup(world);
+        dispatcher
+    }
+
+    fn build_view(world: &mut World, config: &GameConfig) -> Dispatcher<'static, 'static> {
+        let mut dispatcher = DispatcherBuilder::new()
+            .with_pool((*world.read_resource::<ArcThreadPool>()).clone())
+            .with(RenderSystem::new(config.ghost_piece), "render_system", &[])
+            .with(HudSystem::default(), "hud_system", &[])
+            .build();
+        dispatcher.setup(world);
+        dispatcher
+    }
+
+    // Shows the seconds left in the middle of the board, and removes them once the game starts.
+    fn update_countdown(&mut self, world: &mut World) {
+        self.countdown -= world.read_resource::<Time>().delta_seconds();
+        let text = match self.countdown_text {
+            Some(text) => text,
+            None => return,
+        };
+
+        if self.countdown <= 0.0 {
+            world.delete_entity(text).unwrap();
+            self.countdown_text = None;
+            return;
+        }
+        if let Some(ui_text) = world.write_storage::<UiText>().get_mut(text) {
+            let seconds = self.countdown.ceil().to_string();
+            if ui_text.text != seconds {
+                ui_text.text = seconds;
+            }
+        }
+    }
+
+    /*
+    The gameplay runs in frames of the same length, a slow frame on the screen runs a few of them
+    and a fast one might run none. So the game plays the same however fast it is drawn,
+    and a replay can run it again frame by frame. Gives back true once the game is over.
+    */
+    fn run_frames(&mut self, world: &mut World) -> bool {
+        let (dispatcher, reader) = match (self.gameplay.as_mut(), self.game_over_reader.as_mut()) {
+            (Some(dispatcher), Some(reader)) => (dispatcher, reader),
+            _ => return false,
+        };
+
+        self.lag += world.read_resource::<Time>().delta_time();
+        let mut frames = 0;
+        while self.lag >= frame_time() {
+            self.lag -= frame_time();
+            dispatcher.dispatch(world);
+            // Removed blocks have to be gone before the next frame looks for them.
+            world.maintain();
+
+            // When the stack reaches the top or the goal is reached, the frames after it are not played.
+            let game_over_channel = world.read_resource::<EventChannel<GameOverEvent>>();
+            if game_over_channel.read(reader).next().is_some() {
+                return true;
+            }
+
+            frames += 1;
+            if frames == MAX_CATCH_UP {
+                self.lag = Duration::default();
+                break;
+            }
+        }
+        false
+    }
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        Self::new(ModeKind::default())
+    }
+}
+
+impl SimpleState for GameState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let StateData { world, .. } = data;
+
+        // A replay comes with the rules and the seed it was played with,
+        // a new game gets the seed from the command line or the config, or a new one.
+        let (config, handling, seed) = match &self.replay {
+            Some(replay) => (replay.config.clone(), replay.handling.clone(), replay.seed),
+            None => {
+                let config = world.read_resource::<GameConfig>().clone();
+                let handling = world.read_resource::<HandlingConfig>().clone();
+                let seed = config.seed.unwrap_or_else(rand::random);
+                (config, handling, seed)
+            }
+        };
+        // Nothing is held before the first frame, whatever the last game ended with.
+        world.insert(PlayerInput::default());
+        if self.replay.is_none() {
+            world.insert(Replay::new(
+                self.mode,
+                seed,
+                config.clone(),
+                handling.clone(),
+            ));
+        }
+
+        // The whole board lives in this resource, the systems only read and poke it.
+        let level = Level::new(
+            config.start_level,
+            config.lines_per_level,
+            config.gravity.clone(),
+            config.twenty_g_level,
+        );
+        let mut game = Game::new(config.board_width, config.board_height)
+            .with_lock_delay(frames(config.lock_delay), config.lock_resets);
+        game.set_gravity(level.gravity());
+
+        // Some modes do not start on an empty board, the garbage comes from the same seed as the pieces.
+        let mut rng = SeededRng::new(seed);
+        let session = Session::new(self.mode);
+        session.setup(&mut game, &mut rng);
+
+        // The first piece comes from the randomizer like every other one,
+        // and the preview is filled right away, so it can be looked at during the countdown.
+        let mut randomizer = config.randomizer.build();
+        let mut queue = PieceQueue::default();
+        let first = queue.next(config.preview_count, || randomizer.next_piece(&mut rng));
+        game.spawn(Piece::new(first));
+        world.insert(game);
+        world.insert(Score::default());
+        world.insert(level);
+        world.insert(session);
+        world.insert(queue);
+        world.insert(rng);
+
+        // Setup debug lines as a component, they draw the walls and the floor of the board.
+        let mut debug_lines_component = DebugLinesComponent::new();
+        let (width, height) = (config.board_width as f32, config.board_height as f32);
+        let corners = [(0., height), (0., 0.), (width, 0.), (width, height)];
+        for line in corners.windows(2) {
+            let (from, to) = (line[0], line[1]);
+            debug_lines_component.add_line(
+                Point3::new(from.0, from.1, 0.),
+                Point3::new(to.0, to.1, 0.),
+                Srgba::new(0.9, 0.9, 0.9, 1.0),
+            );
+        }
+        world.register::<DebugLinesComponent>();
+        world.create_entity().with(debug_lines_component).build();
+
+        // Like I said, data-driven means you have to set up a place for data to store.
+        // We put the event channel once the state is run.
+        let land_channel = EventChannel::<PieceLandEvent>::new();
+        world.insert(land_channel);
+        world.insert(EventChannel::<LineClearEvent>::new());
+        let mut game_over_channel = EventChannel::<GameOverEvent>::new();
+        self.game_over_reader = Some(game_over_channel.register_reader());
+        world.insert(game_over_channel);
+
+        create_camera(world, config.board_width, config.board_height);
+
+        // Make sure that we have loaded the assets.
+        let texture_handle = {
+            let loader = world.read_resource::<Loader>();
+            loader.load(
+                "sprites/tetriminos/tetris_block.png",
+                ImageFormat::default(),
+                (),
+                &world.read_resource::<AssetStorage<Texture>>(),
+            )
+        };
+
+        let spritesheet_handle = {
+            let loader = world.read_resource::<Loader>();
+            loader.load(
+                "sprites/tetriminos/sprites.ron",
+                SpriteSheetFormat(texture_handle),
+                (),
+                &world.read_resource::<AssetStorage<SpriteSheet>>(),
+            )
+        };
+        world.insert(spritesheet_handle);
+        initialise_audio(world);
+        create_ui(world);
+
+        if self.mode.timed() {
+            self.countdown = COUNTDOWN;
+            self.countdown_text = Some(create_countdown(world));
+        }
+
+        self.gameplay = Some(self.build_gameplay(world, &config, handling, randomizer));
+        self.view = Some(Self::build_view(world, &config));
+
+        // The storages of the blocks are only there once the systems are set up.
+        create_garbage(world);
+    }
+
+    // Pausing pushes a state on top of this one, so update is not called and everything stops until it is popped.
+    fn handle_event(
+        &mut self,
+        _data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Input(InputEvent::ActionPressed(action)) = &event {
+            if action == "pause" {
+                return Trans::Push(Box::new(PausedState::new(self.mode)));
+            }
+        }
+        Trans::None
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        // Nothing moves until the countdown is over.
+        let game_over = if self.countdown > 0.0 {
+            self.update_countdown(data.world);
+            false
+        } else {
+            self.run_frames(data.world)
+        };
+        if let Some(dispatcher) = self.view.as_mut() {
+            dispatcher.dispatch(&data.world);
+        }
+
+        // Stop the game and show how it went, a game that was played is saved so it can be watched again.
+        if game_over {
+            return match self.replay.take() {
+                Some(replay) => Trans::Switch(Box::new(GameOverState::watched(replay))),
+                None => {
+                    let config = data.world.read_resource::<GameConfig>();
+                    data.world.read_resource::<Replay>().save(&config);
+                    Trans::Switch(Box::new(GameOverState::default()))
+                }
+            };
+        }
+
+        Trans::None
+    }
+}
+
+/*
+Also, setting up this camera is necessary, it is quite difficult to do it from scratch.
+One cell of the board is one unit of the world. We pick how many pixels a cell gets so the whole board
+fits left of the panel, then put the board in the middle of that space.
+*/
+fn create_camera(world: &mut World, board_width: usize, board_height: usize) {
+    let (screen_width, screen_height) = {
+        let screen = world.read_resource::<ScreenDimensions>();
+        (screen.width(), screen.height())
+    };
+    let (board_width, board_height) = (board_width as f32, board_height as f32);
+    let area_width = screen_width - PANEL_WIDTH;
+    let cell = (area_width / board_width).min(screen_height / board_height);
+
+    // How much of the world the camera sees, and the empty space around the board in cells.
+    let (view_width, view_height) = (screen_width / cell, screen_height / cell);
+    let margin_left = (area_width / cell - board_width) * 0.5;
+    let margin_bottom = (view_height - board_height) * 0.5;
+
+    let mut transform = Transform::default();
+    transform.set_translation_xyz(
+        view_width * 0.5 - margin_left,
+        view_height * 0.5 - margin_bottom,
+        1.0,
+    );
+    world
+        .create_entity()
+        .with(Camera::standard_2d(view_width, view_height))
+        .with(transform)
+        .build();
+}
+
+// The garbage the mode put on the board gets gray blocks, like the ones of a landed piece.
+fn create_garbage(world: &mut World) {
+    let sprite_sheet = world.read_resource::<Handle<SpriteSheet>>().clone();
+    let garbage = {
+        let game = world.read_resource::<Game>();
+        let board = &game.board;
+        let mut garbage = Vec::new();
+        for row in 0..board.height() as i16 {
+            for col in 0..board.width() as i16 {
+                let position = Position { row, col };
+                if board.get(&position) == Some(Cell::Garbage) {
+                    garbage.push(position);
+                }
+            }
+        }
+        garbage
+    };
+
+    for position in garbage {
+        let mut transform = Transform::default();
+        transform.set_scale(Vector3::new(0.065, 0.065, 1.0));
+        transform.set_translation_xyz(position.col as f32 + 0.5, position.row as f32 + 0.5, 0.0);
+        world
+            .create_entity()
+            .with(GarbageBlock)
+            .with(position)
+            .with(SpriteRender {
+                sprite_sheet: sprite_sheet.clone(),
+                sprite_number: 0,
+            })
+            .with(transform)
+            .with(Tint(GarbageBlock::color()))
+            .build();
+    }
+}
+
+// A big number in the middle of the board.
+fn create_countdown(world: &mut World) -> Entity {
+    let font = load_font(world);
+    world
+        .create_entity()
+        .with(UiTransform::new(
+            "".to_string(),
+            Anchor::Middle,
+            Anchor::Middle,
+            -PANEL_WIDTH * 0.5,
+            0.,
+            5.,
+            200.,
+            120.,
+        ))
+        .with(UiText::new(
+            font,
+            COUNTDOWN.to_string(),
+            [1., 1., 1., 1.],
+            110.,
+            LineMode::Single,
+            Anchor::Middle,
+        ))
+        .build()
+}
+
+// The same font everywhere, the loader gives back the same handle if it was loaded before.
+pub fn load_font(world: &World) -> FontHandle {
+    world.read_resource::<Loader>().load(
+        "fonts/Bangers-Regular.ttf",
+        TtfFormat,
+        (),
+        &world.read_resource(),
+    )
+}
+
+/// Creates a simple UI background and a UI text label
+/// This is the pure code only way to create UI with amethyst.
+pub fn create_ui(world: &mut World) {
+    // this creates the simple pink background UI element.
+    world
+        .create_entity()
+        .with(UiImage::SolidColor([0.6, 0.1, 0.2, 1.0]))
+        .with(UiTransform::new(
+            "".to_string(),
+            Anchor::TopRight,
+            Anchor::TopLeft,
+            -PANEL_WIDTH,
+            0.,
+            0.,
+            PANEL_WIDTH,
+            800.,
+        ))
+        .build();
+
+    // This simply loads a font from the asset folder and puts it in the world as a resource,
+    // we also get a ref to the font that we then can pass to the text label we crate later.
+    let font = load_font(world);
+
+    // This creates the actual label and places it on the screen.
+    // Take note of the z position given, this ensures the label gets rendered above the background UI element.
+    world
+        .create_entity()
+        .with(UiTransform::new(
+            "".to_string(),
+            Anchor::TopRight,
+            Anchor::TopLeft,
+            10. - PANEL_WIDTH,
+            -40.,
+            1.,
+            200.,
+            60.,
+        ))
+        .with(UiText::new(
+            font.clone(),
+            "RUSTRIS".to_string(),
+            [1., 0.5, 1., 1.],
+            55.,
+            LineMode::Single,
+            Anchor::TopLeft,
+        ))
+        .build();
+
+    // Titles of the boxes, the pieces inside are drawn by the HudSystem.
+    for (x, y) in [(-110.0, "HOLD"), (-200.0, "NEXT"), (-460.0, "SCORE")].iter() {
+        world
+            .create_entity()
+            .with(UiTransform::new(
+                "".to_string(),
+                Anchor::TopRight,
+                Anchor::TopLeft,
+                10. - PANEL_WIDTH,
+                *x,
+                1.,
+                200.,
+                40.,
+            ))
+            .with(UiText::new(
+                font.clone(),
+                y.to_string(),
+                [1., 1., 1., 1.],
+                30.,
+                LineMode::Single,
+                Anchor::TopLeft,
+            ))
+            .build();
+    }
+
+    // The HudSystem keeps these up to date.
+    let mut hud_text = Vec::new();
+    for (y, size) in [(-495.0, 30.), (-535.0, 25.), (-565.0, 25.), (-595.0, 25.)].iter() {
+        let entity = world
+            .create_entity()
+            .with(UiTransform::new(
+                "".to_string(),
+                Anchor::TopRight,
+                Anchor::TopLeft,
+                10. - PANEL_WIDTH,
+                *y,
+                1.,
+                200.,
+                40.,
+            ))
+            .with(UiText::new(
+                font.clone(),
+                "".to_string(),
+                [1., 1., 1., 1.],
+                *size,
+                LineMode::Single,
+                Anchor::TopLeft,
+            ))
+            .build();
+        hud_text.push(entity);
+    }
+    world.insert(HudText {
+        score: hud_text[0],
+        level: hud_text[1],
+        lines: hud_text[2],
+        time: hud_text[3],
+    });
+
+    for (x, y) in [
+        (-660.0, "How to play"),
+        (-688.0, "WASD - move"),
+        (-716.0, "K - rotate cw"),
+        (-744.0, "J - rotate ccw"),
+        (-772.0, "L - hold"),
+        (-800.0, "P - pause"),
+    ]
+    .iter()
+    {
+        world
+            .create_entity()
+            .with(UiTransform::new(
+                "".to_string(),
+                Anchor::TopRight,
+                Anchor::TopLeft,
+                10. - PANEL_WIDTH,
+                x.clone() + 30.,
+                1.,
+                200.,
+                500.,
+            ))
+            .with(UiText::new(
+                font.clone(),
+                y.clone().to_string(),
+                [1., 1., 1., 1.],
+                20.,
+                LineMode::Single,
+                Anchor::TopLeft,
+            ))
+            .build();
+    }
+}
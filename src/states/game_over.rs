@@ -0,0 +1,220 @@
+use amethyst::{
+    input::InputEvent,
+    prelude::*,
+    ui::{Anchor, LineMode, UiImage, UiText, UiTransform},
+};
+
+use crate::config::GameConfig;
+use crate::engine::{
+    format_time, Ending, Game, Level, ModeKind, Score, SeededRng, Session, TopOut,
+};
+use crate::records::{self, RecordEntry, Records};
+use crate::replay::Replay;
+use crate::states::{load_font, GameState, ReplayState};
+
+/*
+Nothing runs here, the board stays on the screen under a dark box with the final stats.
+Enter starts a new game of the same mode, Escape goes back to the menu.
+At the end of a replay Enter watches it again, and nothing goes on the records.
+*/
+#[derive(Default)]
+pub struct GameOverState {
+    mode: ModeKind,
+    watched: Option<Replay>,
+}
+
+impl GameOverState {
+    pub fn watched(replay: Replay) -> Self {
+        Self {
+            mode: replay.mode,
+            watched: Some(replay),
+        }
+    }
+}
+
+impl SimpleState for GameOverState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let StateData { world, .. } = data;
+
+        let lines = {
+            let game = world.read_resource::<Game>();
+            let score = world.read_resource::<Score>();
+            let level = world.read_resource::<Level>();
+            let session = world.read_resource::<Session>();
+            let config = world.read_resource::<GameConfig>();
+            self.mode = session.mode;
+
+            let time = session.elapsed();
+            let pps = session.pieces_per_second(game.pieces_locked());
+            let mut lines = match (session.ending(), session.mode) {
+                // A finished sprint or dig is all about the time.
+                (Some(Ending::Finished), ModeKind::Sprint { lines: goal }) => vec![
+                    (format!("{} LINES", goal), 60.),
+                    (format!("Time: {}", format_time(time)), 30.),
+                    (format!("Pieces: {}", game.pieces_locked()), 30.),
+                    (format!("PPS: {:.2}", pps), 30.),
+                ],
+                (Some(Ending::Finished), ModeKind::Dig { rows, .. }) => vec![
+                    (format!("DUG {} ROWS", rows), 60.),
+                    (format!("Time: {}", format_time(time)), 30.),
+                    (format!("Pieces: {}", game.pieces_locked()), 30.),
+                    (format!("PPS: {:.2}", pps), 30.),
+                ],
+                // A marathon is won once the goal is reached, unless it goes on forever.
+                (Some(Ending::Finished), ModeKind::Marathon { .. }) => vec![
+                    ("MARATHON CLEAR".to_string(), 60.),
+                    (format!("Score: {}", score.points), 30.),
+                    (
+                        format!("Lines: {}  Level: {}", game.lines_cleared(), level.level),
+                        30.,
+                    ),
+                    (format!("Time: {}", format_time(time)), 30.),
+                    (
+                        format!("Pieces: {}  PPS: {:.2}", game.pieces_locked(), pps),
+                        30.,
+                    ),
+                ],
+                // Ultra is all about the score.
+                (Some(Ending::TimeUp), ModeKind::Ultra { .. }) => vec![
+                    ("TIME UP".to_string(), 60.),
+                    (format!("Score: {}", score.points), 30.),
+                    (format!("Lines: {}", game.lines_cleared()), 30.),
+                    (
+                        format!("Pieces: {}  PPS: {:.2}", game.pieces_locked(), pps),
+                        30.,
+                    ),
+                ],
+                (ending, _) => {
+                    let mut reason = match ending {
+                        Some(Ending::TopOut(TopOut::LockOut)) => "Lock out".to_string(),
+                        _ => "Block out".to_string(),
+                    };
+                    // An endless marathon still remembers when it was won.
+                    if let Some(goal_time) = session.goal_time() {
+                        reason = format!("{}, goal in {}", reason, format_time(goal_time));
+                    }
+                    vec![
+                        ("GAME OVER".to_string(), 60.),
+                        (reason, 30.),
+                        (format!("Score: {}", score.points), 30.),
+                        (format!("Pieces: {}", game.pieces_locked()), 30.),
+                        (
+                            format!("Lines: {}  Level: {}", game.lines_cleared(), level.level),
+                            30.,
+                        ),
+                    ]
+                }
+            };
+
+            // Where the game ended up on the leaderboard of the mode.
+            let mut records = Records::read(&config);
+            // A replay was played before, it is already on there if it made it.
+            let counts = self.watched.is_none() && records::counts(session.mode, session.ending());
+            let place = if counts {
+                let entry = RecordEntry::new(
+                    &config.player_name,
+                    score.points,
+                    game.lines_cleared(),
+                    time,
+                );
+                records.add(session.mode, entry)
+            } else {
+                None
+            };
+            if place.is_some() {
+                records.save();
+            }
+            let record = match place {
+                Some(0) => Some("New personal best!".to_string()),
+                Some(place) => Some(format!("Number {} in the records", place + 1)),
+                None => records
+                    .top(session.mode)
+                    .first()
+                    .map(|best| format!("Best: {}", best.result(session.mode))),
+            };
+            if let Some(record) = record {
+                lines.push((record, 30.));
+            }
+            // With the seed someone else can play the same pieces.
+            let seed = world.read_resource::<SeededRng>().seed();
+            lines.push((format!("Seed: {}", seed), 20.));
+
+            let again = if self.watched.is_some() {
+                "Enter - watch again"
+            } else {
+                "Enter - play again"
+            };
+            lines.push((again.to_string(), 25.));
+            lines.push(("Esc - menu".to_string(), 25.));
+            lines
+        };
+
+        world
+            .create_entity()
+            .with(UiImage::SolidColor([0.0, 0.0, 0.0, 0.8]))
+            .with(UiTransform::new(
+                "".to_string(),
+                Anchor::Middle,
+                Anchor::Middle,
+                0.,
+                0.,
+                5.,
+                360.,
+                520.,
+            ))
+            .build();
+
+        let font = load_font(world);
+        let mut y = 220.;
+        for (text, size) in lines {
+            world
+                .create_entity()
+                .with(UiTransform::new(
+                    "".to_string(),
+                    Anchor::Middle,
+                    Anchor::Middle,
+                    0.,
+                    y,
+                    6.,
+                    340.,
+                    size + 10.,
+                ))
+                .with(UiText::new(
+                    font.clone(),
+                    text,
+                    [1., 1., 1., 1.],
+                    size,
+                    LineMode::Single,
+                    Anchor::Middle,
+                ))
+                .build();
+            y -= size + 20.;
+        }
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Input(InputEvent::ActionPressed(action)) = &event {
+            match action.as_str() {
+                // Start from an empty world, the game state builds everything again.
+                "menu_select" => {
+                    data.world.delete_all();
+                    return match self.watched.take() {
+                        Some(replay) => Trans::Switch(Box::new(ReplayState::new(replay))),
+                        None => Trans::Switch(Box::new(GameState::new(self.mode))),
+                    };
+                }
+                // The menu is under the game on the stack, it shows itself again.
+                "menu_back" => {
+                    data.world.delete_all();
+                    return Trans::Pop;
+                }
+                _ => {}
+            }
+        }
+        Trans::None
+    }
+}
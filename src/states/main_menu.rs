@@ -0,0 +1,304 @@
+use amethyst::{
+    ecs::Entity,
+    input::InputEvent,
+    prelude::*,
+    ui::{Anchor, LineMode, UiText, UiTransform},
+};
+
+use crate::config::GameConfig;
+use crate::engine::{MarathonGoal, ModeKind};
+use crate::records::{format_date, Records};
+use crate::replay::Replay;
+use crate::states::{load_font, GameState, Menu, ReplayState};
+
+// The usual modes, the one from gameplay.ron comes first if it is not one of them.
+const MODES: [ModeKind; 5] = [
+    ModeKind::Marathon {
+        goal: MarathonGoal::Lines(150),
+        endless: false,
+    },
+    ModeKind::Sprint { lines: 40 },
+    ModeKind::Ultra { seconds: 120 },
+    ModeKind::Dig {
+        rows: 10,
+        messiness: 30,
+    },
+    ModeKind::Endless,
+];
+
+const SETTINGS: usize = 3;
+const MAX_PREVIEW: usize = 5;
+const MAX_START_LEVEL: u32 = 15;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Page {
+    Main,
+    Play,
+    Settings,
+    Records,
+    RecordTable(usize), // the records of one of the modes on the records page
+}
+
+/*
+The first thing on the screen. A game is pushed on top of the menu, so when it is over we come back here.
+Everything is picked with the menu actions, so it works the same with the keyboard and a gamepad.
+*/
+pub struct MainMenuState {
+    page: Page,
+    menu: Option<Menu>,
+    texts: Vec<Entity>,
+    modes: Vec<ModeKind>, // the modes on the play or the records page
+}
+
+impl Default for MainMenuState {
+    fn default() -> Self {
+        Self {
+            page: Page::Main,
+            menu: None,
+            texts: Vec::new(),
+            modes: Vec::new(),
+        }
+    }
+}
+
+impl MainMenuState {
+    fn clear(&mut self, world: &mut World) {
+        if let Some(menu) = self.menu.take() {
+            menu.delete(world);
+        }
+        // They are already gone if the game cleared the whole world.
+        world.delete_entities(&self.texts).ok();
+        self.texts.clear();
+    }
+
+    fn show(&mut self, world: &mut World, page: Page) {
+        self.clear(world);
+        self.page = page;
+
+        let (title, entries, lines) = match page {
+            Page::Main => (
+                "RUSTRIS".to_string(),
+                vec![
+                    "Play".to_string(),
+                    "Watch last game".to_string(),
+                    "Settings".to_string(),
+                    "Records".to_string(),
+                    "Quit".to_string(),
+                ],
+                Vec::new(),
+            ),
+            Page::Play => {
+                self.modes = playable_modes(&world.read_resource::<GameConfig>());
+                let mut entries = self
+                    .modes
+                    .iter()
+                    .map(ModeKind::to_string)
+                    .collect::<Vec<_>>();
+                entries.push("Back".to_string());
+                ("PLAY".to_string(), entries, Vec::new())
+            }
+            Page::Settings => {
+                let mut entries = settings(&world.read_resource::<GameConfig>()).to_vec();
+                entries.push("Back".to_string());
+                // The files are not written, comments in there would be lost.
+                let note = vec!["Kept until the game is closed".to_string()];
+                ("SETTINGS".to_string(), entries, note)
+            }
+            Page::Records => {
+                let records = Records::read(&world.read_resource::<GameConfig>());
+                self.modes = records
+                    .modes
+                    .iter()
+                    .filter(|records| !records.entries.is_empty())
+                    .map(|records| records.mode)
+                    .collect();
+                let mut entries = self
+                    .modes
+                    .iter()
+                    .map(ModeKind::to_string)
+                    .collect::<Vec<_>>();
+                entries.push("Back".to_string());
+                let lines = if self.modes.is_empty() {
+                    vec!["No records yet".to_string()]
+                } else {
+                    Vec::new()
+                };
+                ("RECORDS".to_string(), entries, lines)
+            }
+            Page::RecordTable(index) => {
+                let mode = self.modes[index];
+                let records = Records::read(&world.read_resource::<GameConfig>());
+                (
+                    mode.to_string(),
+                    vec!["Back".to_string()],
+                    record_lines(&records, mode),
+                )
+            }
+        };
+
+        let font = load_font(world);
+        let mut y = 280.;
+        let mut texts = vec![(title, 50.)];
+        texts.extend(lines.into_iter().map(|line| (line, 25.)));
+        for (text, size) in texts {
+            let entity = world
+                .create_entity()
+                .with(UiTransform::new(
+                    "".to_string(),
+                    Anchor::Middle,
+                    Anchor::Middle,
+                    0.,
+                    y,
+                    6.,
+                    500.,
+                    size + 10.,
+                ))
+                .with(UiText::new(
+                    font.clone(),
+                    text,
+                    [1., 1., 1., 1.],
+                    size,
+                    LineMode::Single,
+                    Anchor::Middle,
+                ))
+                .build();
+            self.texts.push(entity);
+            y -= size + 15.;
+        }
+
+        let entries = entries.iter().map(String::as_str).collect::<Vec<_>>();
+        self.menu = Some(Menu::create(world, &entries, y - 30.));
+    }
+
+    // Picking a setting moves it to the next value, it is used by the next game.
+    fn change_setting(&mut self, world: &mut World, index: usize) {
+        let text = {
+            let mut config = world.write_resource::<GameConfig>();
+            match index {
+                0 => config.ghost_piece = !config.ghost_piece,
+                1 => config.preview_count = (config.preview_count + 1) % (MAX_PREVIEW + 1),
+                _ => config.start_level = config.start_level % MAX_START_LEVEL + 1,
+            }
+            settings(&config)[index].clone()
+        };
+        if let Some(menu) = self.menu.as_ref() {
+            menu.set_text(world, index, text);
+        }
+    }
+}
+
+// The ones that make sense with the current config, a dig can need a higher board for example.
+fn playable_modes(config: &GameConfig) -> Vec<ModeKind> {
+    let mut modes = Vec::new();
+    for mode in Some(config.mode).iter().chain(MODES.iter()) {
+        let mut check = config.clone();
+        check.mode = *mode;
+        if !modes.contains(mode) && check.validate().is_ok() {
+            modes.push(*mode);
+        }
+    }
+    modes
+}
+
+fn settings(config: &GameConfig) -> [String; SETTINGS] {
+    [
+        format!(
+            "Ghost piece: {}",
+            if config.ghost_piece { "On" } else { "Off" }
+        ),
+        format!("Preview: {}", config.preview_count),
+        format!("Start level: {}", config.start_level),
+    ]
+}
+
+// One line for every entry, with the best one on top.
+fn record_lines(records: &Records, mode: ModeKind) -> Vec<String> {
+    records
+        .top(mode)
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            format!(
+                "{}. {}  {}  {} lines  {}",
+                index + 1,
+                entry.name,
+                entry.result(mode),
+                entry.lines,
+                format_date(entry.date)
+            )
+        })
+        .collect()
+}
+
+impl SimpleState for MainMenuState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.show(data.world, Page::Main);
+    }
+
+    fn on_pause(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        self.clear(data.world);
+    }
+
+    // Back from a game, on the same page it was started from.
+    fn on_resume(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let page = self.page;
+        self.show(data.world, page);
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        let world = data.world;
+        if let StateEvent::Input(InputEvent::ActionPressed(action)) = &event {
+            let back = match self.page {
+                Page::Main => None,
+                Page::RecordTable(_) => Some(Page::Records),
+                _ => Some(Page::Main),
+            };
+            if let (Some(page), "menu_back") = (back, action.as_str()) {
+                self.show(world, page);
+                return Trans::None;
+            }
+        }
+
+        let picked = match self.menu.as_mut() {
+            Some(menu) => menu.handle_event(world, &event),
+            None => None,
+        };
+        let index = match picked {
+            Some(index) => index,
+            None => return Trans::None,
+        };
+
+        match self.page {
+            Page::Main => match index {
+                0 => self.show(world, Page::Play),
+                1 => {
+                    let replay = Replay::latest(&world.read_resource::<GameConfig>());
+                    match replay {
+                        Some(replay) => return Trans::Push(Box::new(ReplayState::new(replay))),
+                        None => {
+                            if let Some(menu) = self.menu.as_ref() {
+                                menu.set_text(world, index, "No game to watch yet".to_string());
+                            }
+                        }
+                    }
+                }
+                2 => self.show(world, Page::Settings),
+                3 => self.show(world, Page::Records),
+                _ => return Trans::Quit,
+            },
+            Page::Play => match self.modes.get(index) {
+                Some(mode) => return Trans::Push(Box::new(GameState::new(*mode))),
+                None => self.show(world, Page::Main),
+            },
+            Page::Settings if index < SETTINGS => self.change_setting(world, index),
+            Page::Records if index < self.modes.len() => self.show(world, Page::RecordTable(index)),
+            Page::Settings | Page::Records => self.show(world, Page::Main),
+            Page::RecordTable(_) => self.show(world, Page::Records),
+        }
+        Trans::None
+    }
+}
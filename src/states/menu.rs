@@ -0,0 +1,105 @@
+use amethyst::{
+    ecs::Entity,
+    input::InputEvent,
+    prelude::*,
+    ui::{Anchor, LineMode, UiText, UiTransform},
+};
+
+use crate::states::load_font;
+
+const SELECTED_COLOR: [f32; 4] = [1., 0.5, 1., 1.];
+const COLOR: [f32; 4] = [1., 1., 1., 1.];
+const SPACING: f32 = 50.;
+
+/*
+A list of entries in the middle of the window, one of them is selected.
+The states that show a menu pass their events here and only hear back when an entry is picked.
+*/
+pub struct Menu {
+    entries: Vec<Entity>,
+    selected: usize,
+}
+
+impl Menu {
+    // The first entry is centered on `top` and selected, the others go down from there.
+    pub fn create(world: &mut World, entries: &[&str], top: f32) -> Self {
+        let font = load_font(world);
+        let entries = entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                world
+                    .create_entity()
+                    .with(UiTransform::new(
+                        "".to_string(),
+                        Anchor::Middle,
+                        Anchor::Middle,
+                        0.,
+                        top - index as f32 * SPACING,
+                        7.,
+                        340.,
+                        40.,
+                    ))
+                    .with(UiText::new(
+                        font.clone(),
+                        entry.to_string(),
+                        if index == 0 { SELECTED_COLOR } else { COLOR },
+                        30.,
+                        LineMode::Single,
+                        Anchor::Middle,
+                    ))
+                    .build()
+            })
+            .collect();
+        Self {
+            entries,
+            selected: 0,
+        }
+    }
+
+    fn select(&mut self, world: &mut World, selected: usize) {
+        let mut texts = world.write_storage::<UiText>();
+        for (index, entry) in self.entries.iter().enumerate() {
+            if let Some(text) = texts.get_mut(*entry) {
+                text.color = if index == selected {
+                    SELECTED_COLOR
+                } else {
+                    COLOR
+                };
+            }
+        }
+        self.selected = selected;
+    }
+
+    // Changes what an entry says, like a setting that was switched.
+    pub fn set_text(&self, world: &mut World, index: usize, text: String) {
+        let entry = match self.entries.get(index) {
+            Some(entry) => *entry,
+            None => return,
+        };
+        if let Some(ui_text) = world.write_storage::<UiText>().get_mut(entry) {
+            ui_text.text = text;
+        }
+    }
+
+    /// Moves the selection up and down, gives back the selected entry once it is picked.
+    pub fn handle_event(&mut self, world: &mut World, event: &StateEvent) -> Option<usize> {
+        let action = match event {
+            StateEvent::Input(InputEvent::ActionPressed(action)) => action.as_str(),
+            _ => return None,
+        };
+        let count = self.entries.len();
+        match action {
+            "menu_up" => self.select(world, (self.selected + count - 1) % count),
+            "menu_down" => self.select(world, (self.selected + 1) % count),
+            "menu_select" => return Some(self.selected),
+            _ => {}
+        }
+        None
+    }
+
+    pub fn delete(&self, world: &mut World) {
+        // They are already gone if the whole world was cleared.
+        world.delete_entities(&self.entries).ok();
+    }
+}
@@ -0,0 +1,15 @@
+mod game;
+mod game_over;
+mod main_menu;
+mod menu;
+mod paused;
+mod replay;
+
+pub use self::{
+    game::{load_font, GameState},
+    game_over::GameOverState,
+    main_menu::MainMenuState,
+    menu::Menu,
+    paused::PausedState,
+    replay::ReplayState,
+};
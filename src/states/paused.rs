@@ -0,0 +1,164 @@
+use amethyst::{
+    core::Hidden,
+    ecs::{Entities, Entity, Join, ReadStorage, WriteStorage},
+    input::InputEvent,
+    prelude::*,
+    renderer::SpriteRender,
+    ui::{Anchor, LineMode, UiImage, UiText, UiTransform},
+};
+
+use crate::config::GameConfig;
+use crate::engine::ModeKind;
+use crate::replay::Replay;
+use crate::states::{load_font, GameState, Menu};
+use crate::systems::HudBlock;
+
+const RESUME: usize = 0;
+const RESTART: usize = 1;
+
+/*
+Pushed on top of the GameState, so nothing in the game runs and the clock stands still.
+The board and the preview are hidden while it is paused, otherwise the player could stop and think for free.
+*/
+pub struct PausedState {
+    mode: ModeKind,
+    menu: Option<Menu>,
+    overlay: Vec<Entity>,
+    hidden: Vec<Entity>, // what we hid, blocks in the buffer were hidden before and have to stay that way
+}
+
+impl PausedState {
+    pub fn new(mode: ModeKind) -> Self {
+        Self {
+            mode,
+            menu: None,
+            overlay: Vec::new(),
+            hidden: Vec::new(),
+        }
+    }
+
+    fn hide_board(&mut self, world: &mut World) {
+        let (entities, sprites, hud_blocks, mut hiddens): (
+            Entities,
+            ReadStorage<SpriteRender>,
+            ReadStorage<HudBlock>,
+            WriteStorage<Hidden>,
+        ) = world.system_data();
+
+        let blocks = (&entities, &sprites, !&hiddens)
+            .join()
+            .map(|(entity, _, _)| entity)
+            .chain(
+                (&entities, &hud_blocks, !&hiddens)
+                    .join()
+                    .map(|(entity, _, _)| entity),
+            )
+            .collect::<Vec<_>>();
+        for entity in blocks {
+            hiddens.insert(entity, Hidden).unwrap();
+            self.hidden.push(entity);
+        }
+    }
+}
+
+impl SimpleState for PausedState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let StateData { world, .. } = data;
+        self.hide_board(world);
+
+        let font = load_font(world);
+        let background = world
+            .create_entity()
+            .with(UiImage::SolidColor([0.0, 0.0, 0.0, 0.8]))
+            .with(UiTransform::new(
+                "".to_string(),
+                Anchor::Middle,
+                Anchor::Middle,
+                0.,
+                0.,
+                5.,
+                360.,
+                300.,
+            ))
+            .build();
+        let title = world
+            .create_entity()
+            .with(UiTransform::new(
+                "".to_string(),
+                Anchor::Middle,
+                Anchor::Middle,
+                0.,
+                90.,
+                6.,
+                340.,
+                70.,
+            ))
+            .with(UiText::new(
+                font,
+                "PAUSED".to_string(),
+                [1., 1., 1., 1.],
+                60.,
+                LineMode::Single,
+                Anchor::Middle,
+            ))
+            .build();
+        self.overlay = vec![background, title];
+        self.menu = Some(Menu::create(
+            world,
+            &["Resume", "Restart", "Quit to menu"],
+            10.,
+        ));
+    }
+
+    fn on_stop(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let StateData { world, .. } = data;
+        if let Some(menu) = self.menu.take() {
+            menu.delete(world);
+        }
+        world.delete_entities(&self.overlay).ok();
+
+        let mut hiddens = world.write_storage::<Hidden>();
+        for entity in self.hidden.drain(..) {
+            hiddens.remove(entity);
+        }
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        // The pause key lets go of the pause as well.
+        if let StateEvent::Input(InputEvent::ActionPressed(action)) = &event {
+            if action == "pause" {
+                return Trans::Pop;
+            }
+        }
+
+        let menu = match self.menu.as_mut() {
+            Some(menu) => menu,
+            None => return Trans::None,
+        };
+        let picked = menu.handle_event(data.world, &event);
+        if picked.is_some() && picked != Some(RESUME) {
+            // The game ends here, it is saved like one that was lost or won.
+            // A replay that is watched cannot be paused, so this is always a game that was played.
+            data.world
+                .read_resource::<Replay>()
+                .save(&data.world.read_resource::<GameConfig>());
+            // Start from an empty world, the game state or the menu builds everything again.
+            data.world.delete_all();
+            self.hidden.clear();
+        }
+        match picked {
+            Some(RESUME) => Trans::Pop,
+            Some(RESTART) => Trans::Sequence(vec![
+                Trans::Pop,
+                Trans::Switch(Box::new(GameState::new(self.mode))),
+            ]),
+            // Both this and the game go, the menu is under them.
+            Some(_) => Trans::Sequence(vec![Trans::Pop, Trans::Pop]),
+            None => Trans::None,
+        }
+    }
+}
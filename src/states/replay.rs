@@ -0,0 +1,72 @@
+use amethyst::{
+    input::InputEvent,
+    prelude::*,
+    ui::{Anchor, LineMode, UiText, UiTransform},
+};
+
+use crate::replay::Replay;
+use crate::states::{load_font, GameState};
+
+/*
+Plays a saved game back. It is the GameState of the replay, which runs the same systems with the same seed
+and the recorded input instead of the keyboard, so every piece lands where it did.
+There is no pause, Escape stops watching and goes back to the menu.
+*/
+pub struct ReplayState {
+    game: GameState,
+}
+
+impl ReplayState {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            game: GameState::watch(replay),
+        }
+    }
+}
+
+impl SimpleState for ReplayState {
+    fn on_start(&mut self, data: StateData<'_, GameData<'_, '_>>) {
+        let font = load_font(data.world);
+        data.world
+            .create_entity()
+            .with(UiTransform::new(
+                "".to_string(),
+                Anchor::TopLeft,
+                Anchor::TopLeft,
+                20.,
+                -20.,
+                5.,
+                300.,
+                50.,
+            ))
+            .with(UiText::new(
+                font,
+                "REPLAY".to_string(),
+                [1., 0.5, 1., 1.],
+                40.,
+                LineMode::Single,
+                Anchor::TopLeft,
+            ))
+            .build();
+        self.game.on_start(data);
+    }
+
+    fn handle_event(
+        &mut self,
+        data: StateData<'_, GameData<'_, '_>>,
+        event: StateEvent,
+    ) -> SimpleTrans {
+        if let StateEvent::Input(InputEvent::ActionPressed(action)) = &event {
+            // The menu is under the replay on the stack, it shows itself again.
+            if action == "menu_back" {
+                data.world.delete_all();
+                return Trans::Pop;
+            }
+        }
+        Trans::None
+    }
+
+    fn update(&mut self, data: &mut StateData<'_, GameData<'_, '_>>) -> SimpleTrans {
+        self.game.update(data)
+    }
+}
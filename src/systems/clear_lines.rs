@@ -1,23 +1,22 @@
 use amethyst::{
     derive::SystemDesc,
     ecs::{
-        prelude::{Join, ReaderId, System, SystemData, Write, WriteStorage},
+        prelude::{Join, ReadStorage, ReaderId, System, SystemData, Write, WriteStorage},
         Entities,
     },
     shrev::EventChannel,
 };
 
 use crate::audio::{play_clear_sound, Sounds};
-use crate::constants::BOARD_WIDTH;
-use crate::entities::{DroppedPiece, Position};
-use crate::events::PieceLandEvent;
+use crate::engine::{Game, Position};
+use crate::entities::{hide_above_field, DroppedPiece, GarbageBlock};
+use crate::events::{LineClearEvent, PieceLandEvent};
 use amethyst::assets::AssetStorage;
 use amethyst::audio::output::Output;
 use amethyst::audio::Source;
 use amethyst::core::ecs::{Read, ReadExpect};
-use amethyst::core::Transform;
-use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use amethyst::core::{Hidden, Transform};
+use std::collections::BTreeSet;
 
 #[derive(SystemDesc)]
 pub struct LineClearSystem {
@@ -37,15 +36,20 @@ impl LineClearSystem {
 
 /*
 Always define the system data, Amethyst will find where you store them. You just tell them what you need.
-So we want to write new stuffs on to the dropped_pieces.
+The game has already cleared the rows on its board, so we only have to make the dropped pieces match it.
+Then the mode hears how many rows went away, and how many of them had garbage in them.
 */
 impl<'s> System<'s> for LineClearSystem {
     type SystemData = (
-        WriteStorage<'s, DroppedPiece>,
+        ReadStorage<'s, DroppedPiece>,
+        ReadStorage<'s, GarbageBlock>,
         WriteStorage<'s, Position>,
         WriteStorage<'s, Transform>,
+        WriteStorage<'s, Hidden>,
+        ReadExpect<'s, Game>,
         Entities<'s>,
         Write<'s, EventChannel<PieceLandEvent>>,
+        Write<'s, EventChannel<LineClearEvent>>,
         Read<'s, AssetStorage<Source>>,
         ReadExpect<'s, Sounds>,
         Option<Read<'s, Output>>,
@@ -54,11 +58,15 @@ impl<'s> System<'s> for LineClearSystem {
     fn run(
         &mut self,
         (
-            mut dropped_pieces,
+            dropped_pieces,
+            garbage_blocks,
             mut positions,
             mut transforms,
+            mut hiddens,
+            game,
             entities,
             mut land_channel,
+            mut line_clear_channel,
             storage,
             sounds,
             audio_output,
@@ -69,63 +77,46 @@ impl<'s> System<'s> for LineClearSystem {
             .get_or_insert_with(|| land_channel.register_reader());
 
         //Keep reading the land channel for any changes.
-        for _ in land_channel.read(reader_id) {
-            let mut drop_pos_row = HashMap::new();
-
-            //store everything into hashmap we will use it to see where all the dropped pieces are at.
-            for (entity, _, dropped_pos, dropped_transform) in (
-                &*entities,
-                &mut dropped_pieces,
-                &mut positions,
-                &mut transforms,
-            )
-                .join()
-            {
-                drop_pos_row
-                    .entry(dropped_pos.row)
-                    .or_insert_with(Vec::new)
-                    .push((entity, dropped_pos, dropped_transform));
+        for event in land_channel.read(reader_id) {
+            if event.cleared_rows.is_empty() {
+                continue;
             }
 
-            let mut rows_to_descend: HashMap<i8, i8> = HashMap::new();
-
-            // Check if we can clear the lines or not? If so, remove the entities
-            for dropped_row in drop_pos_row.keys() {
-                if let Some(pieces_to_clear) = drop_pos_row.get(dropped_row) {
-                    if pieces_to_clear.len() >= BOARD_WIDTH as usize {
-
-                        for block_to_destroy in pieces_to_clear {
-                            entities.delete(block_to_destroy.0).unwrap();
-                        }
-                        play_clear_sound(&*sounds, &storage, audio_output.as_deref());
+            // Remove the blocks of the cleared rows, along with pushing down the upper rows.
+            let mut garbage_rows = BTreeSet::new();
+            for (entity, dropped_pos, dropped_transform) in
+                (&*entities, &mut positions, &mut transforms).join()
+            {
+                let garbage = garbage_blocks.contains(entity);
+                if !garbage && !dropped_pieces.contains(entity) {
+                    continue;
+                }
 
-                        for other_row in drop_pos_row.keys().filter(|x| x > &dropped_row) {
-                            match rows_to_descend.entry(*other_row) {
-                                Entry::Vacant(e) => {
-                                    e.insert(1);
-                                }
-                                Entry::Occupied(mut e) => {
-                                    *e.get_mut() += 1;
-                                }
-                            }
-                        }
+                if event.cleared_rows.contains(&dropped_pos.row) {
+                    if garbage {
+                        garbage_rows.insert(dropped_pos.row);
                     }
+                    entities.delete(entity).unwrap();
+                    continue;
                 }
-            }
-            
-            /*
-            along with pushing down the upper row.
-            */
-            for row_to_descend in rows_to_descend {
-                if let Some(pieces_to_move) = drop_pos_row.get_mut(&row_to_descend.0) {
-                    for piece_to_move in pieces_to_move {
-                        piece_to_move.1.row -= row_to_descend.1;
-                        piece_to_move
-                            .2
-                            .prepend_translation_y(-(row_to_descend.1 as f32));
-                    }
+
+                let rows_to_descend = event
+                    .cleared_rows
+                    .iter()
+                    .filter(|row| **row < dropped_pos.row)
+                    .count() as i16;
+                if rows_to_descend > 0 {
+                    dropped_pos.row -= rows_to_descend;
+                    dropped_transform.prepend_translation_y(-(rows_to_descend as f32));
+                    hide_above_field(entity, dropped_pos, game.visible_height(), &mut hiddens);
                 }
             }
+
+            line_clear_channel.single_write(LineClearEvent {
+                lines: event.cleared_rows.len() as u32,
+                garbage_lines: garbage_rows.len() as u32,
+            });
+            play_clear_sound(&*sounds, &storage, audio_output.as_deref());
         }
     }
 }
@@ -1,16 +1,17 @@
-use crate::entities::{Piece, Position};
+use crate::engine::Game;
 
 use amethyst::assets::Handle;
 use amethyst::core::ecs::{Component, DenseVecStorage, Entities, ReadExpect};
 use amethyst::core::Transform;
 // use amethyst::renderer::palette::Srgba;
 use amethyst::renderer::resources::Tint;
+use amethyst::renderer::transparent::Transparent;
 use amethyst::renderer::{SpriteRender, SpriteSheet};
 use amethyst::{
     core::math::Vector3,
     // core::math::{Point2, Point3, Vector3},
     derive::SystemDesc,
-    ecs::prelude::{Join, ReadStorage, System, SystemData, WriteStorage},
+    ecs::prelude::{Join, System, SystemData, WriteStorage},
 };
 
 pub struct PieceImage;
@@ -19,11 +20,20 @@ impl Component for PieceImage {
     type Storage = DenseVecStorage<Self>;
 }
 
+// How much of the piece color is left in the ghost
+const GHOST_ALPHA: f32 = 0.25;
+
 //Rendering also require a system
 #[derive(SystemDesc)]
-pub struct RenderSystem;
+pub struct RenderSystem {
+    show_ghost: bool,
+}
 
 impl RenderSystem {
+    pub fn new(show_ghost: bool) -> Self {
+        Self { show_ghost }
+    }
+
     // we have this to draw a crossed square, it is used for debugging
     // fn draw_crossed_square(
     //     &self,
@@ -52,27 +62,27 @@ impl RenderSystem {
 
 impl<'s> System<'s> for RenderSystem {
     type SystemData = (
-        ReadStorage<'s, Piece>,
+        ReadExpect<'s, Game>,
         WriteStorage<'s, PieceImage>,
-        ReadStorage<'s, Position>,
         WriteStorage<'s, Transform>,
         Entities<'s>,
         WriteStorage<'s, SpriteRender>,
         ReadExpect<'s, Handle<SpriteSheet>>,
         WriteStorage<'s, Tint>,
+        WriteStorage<'s, Transparent>,
     );
 
     fn run(
         &mut self,
         (
-            pieces,
+            game,
             mut pieces_placed,
-            positions,
             mut transforms,
             entities,
             mut sprite_renders,
             sprite_sheet_handle,
             mut tints,
+            mut transparents,
         ): Self::SystemData,
     ) {
         // Remove every tiles
@@ -80,32 +90,58 @@ impl<'s> System<'s> for RenderSystem {
             entities.delete(entity).unwrap();
         }
 
+        let active = match game.active() {
+            Some(active) => active,
+            None => return,
+        };
+        let piece = &active.piece;
+
+        // The ghost is a see-through copy of the piece where a hard drop would land it.
+        let mut tiles = Vec::new();
+        if let Some(ghost_position) = game.drop_position().filter(|_| self.show_ghost) {
+            if ghost_position != active.position {
+                let mut ghost_color = piece.piece_type.get_color();
+                ghost_color.alpha = GHOST_ALPHA;
+                for ghost_pos in piece.get_filled_positions(&ghost_position) {
+                    tiles.push((ghost_pos, Tint(ghost_color), true));
+                }
+            }
+        }
+
         // draw it again from the new piece
-        for (piece, position) in (&pieces, &positions).join() {
-            for self_pos in piece.get_filled_positions(position) {
-                let sprite_render = SpriteRender {
-                    sprite_sheet: sprite_sheet_handle.clone(),
-                    sprite_number: 0,
-                };
-
-                let mut sprite_transform = Transform::default();
-                sprite_transform.set_scale(Vector3::new(0.065, 0.065, 1.0));
-                sprite_transform.set_translation_xyz(
-                    self_pos.col as f32 + 0.5,
-                    self_pos.row as f32 + 0.5,
-                    0.0,
-                );
-
-                let tint = Tint(piece.piece_type.get_color());
-
-                entities
-                    .build_entity()
-                    .with(PieceImage {}, &mut pieces_placed)
-                    .with(sprite_render, &mut sprite_renders)
-                    .with(sprite_transform, &mut transforms)
-                    .with(tint, &mut tints)
-                    .build();
+        for self_pos in piece.get_filled_positions(&active.position) {
+            tiles.push((self_pos, Tint(piece.piece_type.get_color()), false));
+        }
+
+        // Nothing in the buffer above the field is shown.
+        let visible_height = game.visible_height() as i16;
+        tiles.retain(|(tile_pos, _, _)| tile_pos.row < visible_height);
+
+        for (tile_pos, tint, transparent) in tiles {
+            let sprite_render = SpriteRender {
+                sprite_sheet: sprite_sheet_handle.clone(),
+                sprite_number: 0,
+            };
+
+            let mut sprite_transform = Transform::default();
+            sprite_transform.set_scale(Vector3::new(0.065, 0.065, 1.0));
+            // the ghost sits a bit behind, so the real piece covers it when they overlap.
+            sprite_transform.set_translation_xyz(
+                tile_pos.col as f32 + 0.5,
+                tile_pos.row as f32 + 0.5,
+                if transparent { -0.1 } else { 0.0 },
+            );
+
+            let mut builder = entities
+                .build_entity()
+                .with(PieceImage {}, &mut pieces_placed)
+                .with(sprite_render, &mut sprite_renders)
+                .with(sprite_transform, &mut transforms)
+                .with(tint, &mut tints);
+            if transparent {
+                builder = builder.with(Transparent, &mut transparents);
             }
+            builder.build();
         }
     }
 }
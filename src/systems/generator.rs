@@ -1,69 +1,67 @@
 use amethyst::{
-    derive::SystemDesc,
-    ecs::{
-        prelude::{ReaderId, System, SystemData, Write, WriteStorage},
-        Entities,
-    },
+    ecs::prelude::{ReaderId, System, Write, WriteExpect},
     shrev::EventChannel,
 };
 
-use crate::constants::BOARD_HEIGHT;
-use crate::entities::{Piece, Position};
-use crate::events::PieceLandEvent;
+use crate::engine::{Game, Piece, PieceQueue, Randomizer, SeededRng};
+use crate::events::{PieceHoldEvent, PieceLandEvent};
 
-// Just simple rng generator
-#[derive(SystemDesc)]
+/*
+The spawner asks the randomizer from the config which piece comes next,
+and keeps a few of them waiting in the PieceQueue resource so they can be shown.
+The randomizer gets its numbers from the SeededRng resource, the same seed brings the same pieces.
+*/
 pub struct PieceSpawnSystem {
     reader_id: Option<ReaderId<PieceLandEvent>>,
+    hold_reader_id: Option<ReaderId<PieceHoldEvent>>,
+    randomizer: Box<dyn Randomizer>,
+    preview_count: usize,
 }
 
 impl PieceSpawnSystem {
-    pub fn new() -> Self {
-        Self { reader_id: None }
+    pub fn new(randomizer: Box<dyn Randomizer>, preview_count: usize) -> Self {
+        Self {
+            reader_id: None,
+            hold_reader_id: None,
+            randomizer,
+            preview_count,
+        }
     }
 }
 
 impl<'s> System<'s> for PieceSpawnSystem {
     type SystemData = (
-        WriteStorage<'s, Piece>,
+        WriteExpect<'s, Game>,
         Write<'s, EventChannel<PieceLandEvent>>,
-        WriteStorage<'s, Position>,
-        Entities<'s>,
+        Write<'s, EventChannel<PieceHoldEvent>>,
+        Write<'s, PieceQueue>,
+        WriteExpect<'s, SeededRng>,
     );
 
-    fn run(&mut self, (mut pieces, mut land_channel, mut positions, entities): Self::SystemData) {
+    fn run(
+        &mut self,
+        (mut game, mut land_channel, mut hold_channel, mut queue, mut rng): Self::SystemData,
+    ) {
         let reader_id = self
             .reader_id
             .get_or_insert_with(|| land_channel.register_reader());
+        let hold_reader_id = self
+            .hold_reader_id
+            .get_or_insert_with(|| hold_channel.register_reader());
+
+        // when a piece is landed, or went into an empty hold slot, we generate a new one.
+        let landed = land_channel.read(reader_id).count() > 0;
+        let held = hold_channel.read(hold_reader_id).count() > 0;
 
-        // when a piece is landed, we generate a new one.
-        for _ in land_channel.read(reader_id) {
-            let mut b = Piece::new(rand::random());
-            b.rotation = 0;
-            entities
-                .build_entity()
-                .with(b, &mut pieces)
-                .with(
-                    Position {
-                        row: BOARD_HEIGHT as i8 - 4,
-                        col: 3,
-                    },
-                    &mut positions,
-                )
-                .build();
+        let preview_count = self.preview_count;
+        let randomizer = &mut self.randomizer;
+        let mut generate = || randomizer.next_piece(&mut *rng);
+
+        if landed || (held && game.active().is_none()) {
+            game.spawn(Piece::new(queue.next(preview_count, &mut generate)));
         }
+
+        // keep the queue full, so the preview is there even before the first piece lands.
+        queue.fill(preview_count, &mut generate);
     }
-    // PS. In modern tetris, the system is called 7-bag, here is the explaination.
-    /*
-    https://tetris.fandom.com/wiki/Random_Generator
-    
-    Random Generator generates a sequence of all seven one-sided tetrominoes 
-    (I, J, L, O, S, T, Z) permuted randomly, as if they were drawn from a bag. 
-    Then it deals all seven tetrominoes to the piece sequence before generating another bag. 
-    There are 7!, or 5,040, permutations of seven elements, and it is believed that Tetris assigns a nearly equal 
-    probability to each of these, making it much less likely that the player will get an obscenely long run without 
-    a desired tetromino. It can produce a maximum of 12 tetrominoes between one I and the next I, and a run of S and Z 
-    tetrominoes is limited to a maximum of 4. Exception: In Random Generator as implemented in Tetris The Grand Master Ace, 
-    the first piece of the first bag is always I, J, L, or T, just as in the traditional TGM randomizer.
-    */
 }
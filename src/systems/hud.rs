@@ -0,0 +1,187 @@
+use amethyst::{
+    ecs::{
+        prelude::{Join, Read, ReadExpect, System, WriteStorage},
+        Component, DenseVecStorage, Entities, Entity,
+    },
+    ui::{Anchor, UiImage, UiText, UiTransform},
+};
+
+use crate::engine::{
+    format_time, Game, Level, MarathonGoal, ModeKind, Piece, PieceQueue, PieceType, Position, Score, Session,
+};
+
+// Where the HUD pieces go, in pixels from the top right corner of the window where the panel is.
+const HUD_LEFT: f32 = -150.0;
+const HUD_BLOCK_SIZE: f32 = 16.0;
+const HOLD_TOP: f32 = -150.0;
+const NEXT_TOP: f32 = -240.0;
+const NEXT_SPACING: f32 = 40.0;
+
+// Every small block drawn in the side panel, so we know what to remove before drawing again.
+pub struct HudBlock;
+
+impl Component for HudBlock {
+    type Storage = DenseVecStorage<Self>;
+}
+
+// The text labels of the panel that change during the game, they are created along with the rest of the UI.
+pub struct HudText {
+    pub score: Entity,
+    pub level: Entity,
+    pub lines: Entity,
+    pub time: Entity,
+}
+
+// What is shown on the panel right now, we only rebuild the blocks when it changes.
+#[derive(Clone, PartialEq)]
+struct HudContent {
+    hold: Option<PieceType>,
+    can_hold: bool,
+    next: Vec<PieceType>,
+}
+
+/*
+The board is drawn with sprites, but the side panel belongs to the UI,
+so the pieces in there are made of small solid color UI images.
+*/
+#[derive(Default)]
+pub struct HudSystem {
+    shown: Option<HudContent>,
+}
+
+impl HudSystem {
+    // The blocks of a piece in its spawn rotation, moved so the top left block is at (0, 0).
+    fn piece_blocks(piece_type: PieceType) -> Vec<(f32, f32)> {
+        let cells = Piece::new(piece_type).get_filled_positions(&Position { row: 0, col: 0 });
+        let top = cells.iter().map(|pos| pos.row).max().unwrap_or(0);
+        let left = cells.iter().map(|pos| pos.col).min().unwrap_or(0);
+        cells
+            .iter()
+            .map(|pos| ((pos.col - left) as f32, (top - pos.row) as f32))
+            .collect()
+    }
+}
+
+impl<'s> System<'s> for HudSystem {
+    type SystemData = (
+        ReadExpect<'s, Game>,
+        Read<'s, PieceQueue>,
+        Read<'s, Score>,
+        Read<'s, Level>,
+        ReadExpect<'s, Session>,
+        ReadExpect<'s, HudText>,
+        WriteStorage<'s, UiText>,
+        WriteStorage<'s, HudBlock>,
+        WriteStorage<'s, UiTransform>,
+        WriteStorage<'s, UiImage>,
+        Entities<'s>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            game,
+            queue,
+            score,
+            level,
+            session,
+            hud_text,
+            mut ui_texts,
+            mut hud_blocks,
+            mut ui_transforms,
+            mut ui_images,
+            entities,
+        ): Self::SystemData,
+    ) {
+        // A sprint counts down the lines that are left, dig the rows of garbage.
+        // A marathon to a level shows how far away it is, until it is reached.
+        let level_goal = match session.mode {
+            ModeKind::Marathon {
+                goal: MarathonGoal::Level(goal),
+                ..
+            } if session.goal_time().is_none() => Some(goal),
+            _ => None,
+        };
+        let lines = match (session.lines_left(), session.mode) {
+            (Some(left), ModeKind::Dig { .. }) => format!("Garbage left {}", left),
+            (Some(left), _) if left > 0 => format!("Lines left {}", left),
+            _ if level_goal.is_some() => format!("Next level in {}", level.lines_to_next()),
+            _ => format!("Lines {}", game.lines_cleared()),
+        };
+        let level = match level_goal {
+            Some(goal) => format!("Level {}/{}", level.level, goal),
+            None => format!("Level {}", level.level),
+        };
+
+        // Ultra counts down the time instead.
+        let clock = session.time_left().unwrap_or_else(|| session.elapsed());
+
+        // Only touch the text when it is different, otherwise it gets laid out again every frame.
+        for (entity, value) in [
+            (hud_text.score, score.points.to_string()),
+            (hud_text.level, level),
+            (hud_text.lines, lines),
+            (hud_text.time, format_time(clock)),
+        ]
+        .iter()
+        {
+            if let Some(text) = ui_texts.get_mut(*entity) {
+                if text.text != *value {
+                    text.text = value.clone();
+                }
+            }
+        }
+
+        let content = HudContent {
+            hold: game.held(),
+            can_hold: game.can_hold(),
+            next: queue.iter().cloned().collect(),
+        };
+        if self.shown.as_ref() == Some(&content) {
+            return;
+        }
+
+        for (_, entity) in (&hud_blocks, &*entities).join() {
+            entities.delete(entity).unwrap();
+        }
+
+        // The held piece is dimmed while it cannot be swapped.
+        let mut blocks = Vec::new();
+        if let Some(piece_type) = content.hold {
+            let alpha = if content.can_hold { 1.0 } else { 0.4 };
+            blocks.push((piece_type, HOLD_TOP, alpha));
+        }
+        for (index, piece_type) in content.next.iter().enumerate() {
+            blocks.push((*piece_type, NEXT_TOP - index as f32 * NEXT_SPACING, 1.0));
+        }
+
+        for (piece_type, top, alpha) in blocks {
+            let (red, green, blue, _) = piece_type.get_color().into_components();
+            for (col, row) in Self::piece_blocks(piece_type) {
+                entities
+                    .build_entity()
+                    .with(HudBlock, &mut hud_blocks)
+                    .with(
+                        UiTransform::new(
+                            "".to_string(),
+                            Anchor::TopRight,
+                            Anchor::TopLeft,
+                            HUD_LEFT + col * HUD_BLOCK_SIZE,
+                            top - row * HUD_BLOCK_SIZE,
+                            2.,
+                            HUD_BLOCK_SIZE - 1.,
+                            HUD_BLOCK_SIZE - 1.,
+                        ),
+                        &mut ui_transforms,
+                    )
+                    .with(
+                        UiImage::SolidColor([red, green, blue, alpha]),
+                        &mut ui_images,
+                    )
+                    .build();
+            }
+        }
+
+        self.shown = Some(content);
+    }
+}
@@ -0,0 +1,80 @@
+use amethyst::{
+    ecs::prelude::{Read, System, Write, WriteExpect},
+    input::{InputHandler, StringBindings},
+};
+
+use crate::engine::PlayerInput;
+use crate::replay::Replay;
+
+/*
+Reads the keyboard or the gamepad once every frame of the game, for the piece input system,
+and writes it down in the Replay resource so the game can be saved when it is over.
+*/
+#[derive(Default)]
+pub struct InputRecordSystem {
+    frame: u64,
+}
+
+impl<'s> System<'s> for InputRecordSystem {
+    type SystemData = (
+        Read<'s, InputHandler<StringBindings>>,
+        Write<'s, PlayerInput>,
+        WriteExpect<'s, Replay>,
+    );
+
+    fn run(&mut self, (input, mut player_input, mut replay): Self::SystemData) {
+        let action = |name| input.action_is_down(name).unwrap_or(false);
+        // A is the positive side of move_x, so it goes the other way.
+        let movement_input = input.axis_value("move_x").unwrap_or(0.0);
+        let direction = if movement_input > 0.0 {
+            -1
+        } else if movement_input < 0.0 {
+            1
+        } else {
+            0
+        };
+
+        *player_input = PlayerInput {
+            direction,
+            soft_drop: action("drop_soft"),
+            hard_drop: action("drop_hard"),
+            rotate_cw: action("rotate_cw"),
+            rotate_ccw: action("rotate_ccw"),
+            hold: action("swap"),
+        };
+        replay.record(self.frame, *player_input);
+        self.frame += 1;
+    }
+}
+
+// Plays the input of a replay back, frame by frame, instead of the keyboard.
+pub struct ReplayInputSystem {
+    inputs: Vec<(u64, PlayerInput)>,
+    next: usize,
+    frame: u64,
+}
+
+impl ReplayInputSystem {
+    pub fn new(replay: &Replay) -> Self {
+        Self {
+            inputs: replay.inputs.clone(),
+            next: 0,
+            frame: 0,
+        }
+    }
+}
+
+impl<'s> System<'s> for ReplayInputSystem {
+    type SystemData = Write<'s, PlayerInput>;
+
+    fn run(&mut self, mut player_input: Self::SystemData) {
+        while let Some((frame, input)) = self.inputs.get(self.next) {
+            if *frame > self.frame {
+                break;
+            }
+            *player_input = *input;
+            self.next += 1;
+        }
+        self.frame += 1;
+    }
+}
@@ -3,6 +3,10 @@ mod controller;
 mod draw_pieces;
 mod dropping;
 mod generator;
+mod hud;
+mod input;
+mod mode;
+mod score;
 
 pub use self::{
     clear_lines::LineClearSystem,
@@ -10,4 +14,8 @@ pub use self::{
     draw_pieces::{PieceImage, RenderSystem},
     dropping::DroppingSystem,
     generator::PieceSpawnSystem,
+    hud::{HudBlock, HudSystem, HudText},
+    input::{InputRecordSystem, ReplayInputSystem},
+    mode::ModeSystem,
+    score::ScoreSystem,
 };
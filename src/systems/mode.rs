@@ -0,0 +1,49 @@
+use amethyst::{
+    ecs::prelude::{Read, ReadExpect, ReaderId, System, Write, WriteExpect},
+    shrev::EventChannel,
+};
+
+use crate::engine::{Game, Level, Session};
+use crate::events::{GameOverEvent, LineClearEvent};
+
+/*
+The mode controller, it runs the clock of the game one frame at a time and counts the cleared lines and the level,
+then checks if the mode is over, by a top out or by reaching the goal.
+The state stops the game when it sees the event.
+*/
+pub struct ModeSystem {
+    reader_id: Option<ReaderId<LineClearEvent>>,
+}
+
+impl ModeSystem {
+    pub fn new() -> Self {
+        Self { reader_id: None }
+    }
+}
+
+impl<'s> System<'s> for ModeSystem {
+    type SystemData = (
+        ReadExpect<'s, Game>,
+        WriteExpect<'s, Session>,
+        Read<'s, Level>,
+        Write<'s, EventChannel<LineClearEvent>>,
+        Write<'s, EventChannel<GameOverEvent>>,
+    );
+
+    fn run(
+        &mut self,
+        (game, mut session, level, mut line_clear_channel, mut game_over_channel): Self::SystemData,
+    ) {
+        let reader_id = self
+            .reader_id
+            .get_or_insert_with(|| line_clear_channel.register_reader());
+        for event in line_clear_channel.read(reader_id) {
+            session.add_lines(event.lines, event.garbage_lines);
+        }
+        session.set_level(level.level);
+
+        if let Some(ending) = session.update(&game) {
+            game_over_channel.single_write(GameOverEvent { ending });
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use amethyst::{
+    derive::SystemDesc,
+    ecs::prelude::{ReaderId, System, SystemData, Write},
+    shrev::EventChannel,
+};
+
+use crate::engine::{Level, Score};
+use crate::events::PieceLandEvent;
+
+// Every landed piece goes through the score, whether it cleared something or not, the combo depends on it.
+// The cleared lines also count towards the next level.
+#[derive(SystemDesc)]
+pub struct ScoreSystem {
+    reader_id: Option<ReaderId<PieceLandEvent>>,
+}
+
+impl ScoreSystem {
+    pub fn new() -> Self {
+        Self { reader_id: None }
+    }
+}
+
+impl<'s> System<'s> for ScoreSystem {
+    type SystemData = (
+        Write<'s, Score>,
+        Write<'s, Level>,
+        Write<'s, EventChannel<PieceLandEvent>>,
+    );
+
+    fn run(&mut self, (mut score, mut level, mut land_channel): Self::SystemData) {
+        let reader_id = self
+            .reader_id
+            .get_or_insert_with(|| land_channel.register_reader());
+
+        for event in land_channel.read(reader_id) {
+            score.add_drop(event.soft_drop_rows, event.hard_drop_rows);
+            score.add_landing(
+                event.cleared_rows.len(),
+                event.t_spin,
+                event.perfect_clear,
+                level.level,
+            );
+            level.add_lines(event.cleared_rows.len() as u32);
+        }
+    }
+}